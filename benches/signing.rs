@@ -0,0 +1,132 @@
+// This file is part of HTTP Signatures
+
+// HTTP Signatures is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// HTTP Signatures is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarks for creating signatures.
+//!
+//! Each key size is signed twice: once by passing the DER document to `HttpSignature::new`, which
+//! parses the key for every signature, and once with a `SigningKey`, which is parsed up front.
+//! Keys are read from memory, so the difference between the two is the cost of parsing the key.
+//! The same comparison is made for signing an `http::Request` with `with_authorization_header`
+//! and with `with_authorization_header_using`.
+//!
+//! Run with `cargo bench`.
+
+#[macro_use]
+extern crate criterion;
+extern crate http;
+extern crate http_signatures;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+
+use criterion::Criterion;
+use http::Request;
+use http_signatures::prelude::*;
+use http_signatures::{HttpSignature, ShaSize, SignatureAlgorithm, SignatureParams, SigningKey,
+                      REQUEST_TARGET};
+
+const ALGORITHM: SignatureAlgorithm = SignatureAlgorithm::RSA(ShaSize::TwoFiftySix);
+
+fn read_key(path: &str) -> Vec<u8> {
+    let mut key = Vec::new();
+    File::open(path).unwrap().read_to_end(&mut key).unwrap();
+
+    key
+}
+
+fn headers() -> BTreeMap<String, Vec<String>> {
+    let mut headers = BTreeMap::new();
+    headers.insert(REQUEST_TARGET.into(), vec!["post /foo".into()]);
+    headers.insert("host".into(), vec!["example.org".into()]);
+    headers.insert("date".into(), vec!["Tue, 07 Jun 2014 20:51:35 GMT".into()]);
+    headers.insert(
+        "digest".into(),
+        vec!["SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=".into()],
+    );
+
+    headers
+}
+
+fn bench_key(c: &mut Criterion, name: &str, path: &str) {
+    let key = read_key(path);
+
+    c.bench_function(&format!("{} parsing the key per signature", name), move |b| {
+        b.iter(|| {
+            HttpSignature::new("rsa-key-1".into(), key.as_slice(), ALGORITHM, headers())
+                .unwrap()
+                .authorization_header()
+                .unwrap()
+        })
+    });
+
+    let signing_key = SigningKey::new("rsa-key-1".into(), read_key(path).as_slice(), ALGORITHM)
+        .unwrap();
+
+    c.bench_function(&format!("{} with a SigningKey", name), move |b| {
+        b.iter(|| {
+            SignatureParams::new("rsa-key-1".into(), ALGORITHM, headers())
+                .unwrap()
+                .authorization_header_using(&signing_key)
+                .unwrap()
+        })
+    });
+}
+
+fn bench_request(c: &mut Criterion, name: &str, path: &str) {
+    let key = read_key(path);
+
+    c.bench_function(&format!("{} request parsing the key per signature", name), move |b| {
+        b.iter(|| {
+            request()
+                .with_authorization_header("rsa-key-1".into(), key.as_slice(), ALGORITHM)
+                .map(|_| ())
+                .unwrap()
+        })
+    });
+
+    let signing_key = SigningKey::new("rsa-key-1".into(), read_key(path).as_slice(), ALGORITHM)
+        .unwrap();
+
+    c.bench_function(&format!("{} request with a SigningKey", name), move |b| {
+        b.iter(|| {
+            request()
+                .with_authorization_header_using(&signing_key)
+                .map(|_| ())
+                .unwrap()
+        })
+    });
+}
+
+fn request() -> Request<()> {
+    Request::post("http://example.org/foo")
+        .header("Date", "Tue, 07 Jun 2014 20:51:35 GMT")
+        .header("Digest", "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=")
+        .body(())
+        .unwrap()
+}
+
+fn rsa_2048(c: &mut Criterion) {
+    bench_key(c, "rsa-2048", "tests/assets/private.der");
+    bench_request(c, "rsa-2048", "tests/assets/private.der");
+}
+
+fn rsa_4096(c: &mut Criterion) {
+    bench_key(c, "rsa-4096", "tests/assets/private_4096.der");
+    bench_request(c, "rsa-4096", "tests/assets/private_4096.der");
+}
+
+criterion_group!(benches, rsa_2048, rsa_4096);
+criterion_main!(benches);
@@ -0,0 +1,36 @@
+// This file is part of HTTP Signatures
+
+// HTTP Signatures is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// HTTP Signatures is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
+
+// Every way of parsing a Signature or Authorization header must return an Err on garbage, rather
+// than panic.
+
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate http_signatures;
+
+use std::str::from_utf8;
+
+use http_signatures::{peek_key_id, SignatureHeader, SignedHeader};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(header) = from_utf8(data) {
+        let _ = SignedHeader::new(header);
+        let _ = SignedHeader::new_strict(header);
+        let _ = SignedHeader::parse_all(vec![header, header]);
+        let _ = SignatureHeader::parse(header);
+        let _ = peek_key_id(header);
+    }
+});
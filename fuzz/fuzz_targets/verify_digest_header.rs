@@ -0,0 +1,32 @@
+// This file is part of HTTP Signatures
+
+// HTTP Signatures is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// HTTP Signatures is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
+
+// Digest headers come from the request too, so checking one must not panic either.
+
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate http_signatures;
+
+use std::str::from_utf8;
+
+use http_signatures::Digest;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(header) = from_utf8(data) {
+        let _ = Digest::verify_header(header, br#"{"hello": "world"}"#);
+        let _ = header.parse::<Digest>();
+    }
+});
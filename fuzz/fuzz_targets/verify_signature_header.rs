@@ -0,0 +1,50 @@
+// This file is part of HTTP Signatures
+
+// HTTP Signatures is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// HTTP Signatures is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
+
+// Verifying a request with an attacker-controlled signature header must fail cleanly, whatever
+// headers, timestamps and algorithm it names.
+
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate http_signatures;
+
+use std::str::from_utf8;
+
+use http_signatures::prelude::*;
+use http_signatures::{verify, VerificationError};
+
+#[derive(Clone)]
+struct KeyGetter;
+
+impl GetKey for KeyGetter {
+    type Key = &'static [u8];
+    type Error = VerificationError;
+
+    fn get_key(self, _: &str) -> Result<Self::Key, Self::Error> {
+        Ok(b"shared secret")
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(header) = from_utf8(data) {
+        let headers = vec![
+            ("Date", "Tue, 07 Jun 2014 20:51:35 GMT"),
+            ("Digest", "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE="),
+        ];
+
+        let _ = verify("POST", "/foo", Some("bar=baz"), &headers, header, KeyGetter);
+    }
+});
@@ -17,32 +17,318 @@
 
 use std::collections::BTreeMap;
 use std::convert::{TryFrom, TryInto};
-use std::sync::Arc;
+use std::fmt;
 use std::io::Read;
 
-use ring::{digest, hmac, rand, signature};
-use base64::encode;
-use untrusted::Input;
-
 use error::{CreationError, Error};
-use super::{ShaSize, SignatureAlgorithm};
+use key::{check_key_id, SigningKey};
+use super::{unfold_header_value, Base64Variant, SignatureAlgorithm, CREATED, EXPIRES,
+            REQUEST_TARGET};
 use prelude::*;
+use trace;
+
+const DATE: &str = "date";
 
 /// The `HttpSignature` struct, this is the entry point for creating Authorization or Signature
 /// headers. It contains all the values required for generation.
-#[derive(Clone, Debug)]
+///
+/// Everything but the key is held in its `SignatureParams`, which can be kept and inspected on its
+/// own. The `Debug` implementation never prints the key.
+#[derive(Clone)]
 pub struct HttpSignature<T>
 where
     T: Read,
 {
-    /// The keyId field in the header
-    key_id: String,
+    /// Everything about the signature but the key
+    params: SignatureParams,
     /// The key (implementing `Read`) used to sign the request
     key: T,
+}
+
+/// The `SignatureParams` struct holds everything an `HttpSignature` signs with, except for its
+/// key: the key id, the algorithm, and the headers.
+///
+/// Unlike an `HttpSignature`, it is always `Clone` and `Debug`, so it can be stored, compared in
+/// logs, and turned into an `HttpSignature` again with `with_key`.
+#[derive(Clone, Debug)]
+pub struct SignatureParams {
+    /// The keyId field in the header
+    key_id: String,
     /// The algorithm used to sign the request
     algorithm: SignatureAlgorithm,
     /// The headers that will be included in the signature
     headers: BTreeMap<String, Vec<String>>,
+    /// The keys of `headers`, in the order they will be signed
+    header_order: Vec<String>,
+    /// The Unix time the signature was created at, signed as the `(created)` pseudo-header
+    created: Option<u64>,
+    /// The Unix time the signature expires at, signed as the `(expires)` pseudo-header
+    expires: Option<u64>,
+    /// The base64 variant the signature is encoded with
+    base64: Base64Variant,
+    /// Whether signing fails when the signature isn't bound to the request
+    strict_headers: bool,
+}
+
+impl SignatureParams {
+    /// Create the parameters of a signature without a key, for signatures that are only signed
+    /// with a `Signer`, such as a `SigningKey`, through `authorization_header_using` and
+    /// `signature_header_using`.
+    ///
+    /// Headers are handled as they are by `HttpSignature::new`, which Errors in the same cases.
+    ///
+    /// ### Example
+    /// ```rust
+    /// # use std::collections::BTreeMap;
+    /// # use http_signatures::Error;
+    /// use http_signatures::{SignatureAlgorithm, SignatureParams, SigningKey, ShaSize,
+    ///                       REQUEST_TARGET};
+    ///
+    /// # fn run() -> Result<(), Error> {
+    /// let alg = SignatureAlgorithm::HMAC(ShaSize::TwoFiftySix);
+    /// let key = SigningKey::new("hmac-key-1".into(), &b"secret"[..], alg.clone())?;
+    ///
+    /// let mut headers = BTreeMap::new();
+    /// headers.insert(REQUEST_TARGET.into(), vec!["get /".into()]);
+    ///
+    /// let auth_header = SignatureParams::new("hmac-key-1".into(), alg, headers)?
+    ///     .authorization_header_using(&key)?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// #     run().unwrap();
+    /// # }
+    /// ```
+    pub fn new(
+        key_id: String,
+        algorithm: SignatureAlgorithm,
+        headers: BTreeMap<String, Vec<String>>,
+    ) -> Result<Self, CreationError> {
+        if headers.is_empty() {
+            return Err(CreationError::NoHeaders);
+        }
+
+        Self::with_headers(key_id, algorithm, headers)
+    }
+
+    /// Create `SignatureParams` which may have no headers, for signatures that only sign their
+    /// `(created)` timestamp
+    fn with_headers(
+        key_id: String,
+        algorithm: SignatureAlgorithm,
+        headers: BTreeMap<String, Vec<String>>,
+    ) -> Result<Self, CreationError> {
+        check_key_id(&key_id)?;
+
+        let headers = headers
+            .into_iter()
+            .fold(BTreeMap::new(), |mut acc, (key, values)| {
+                acc.entry(key.to_lowercase())
+                    .or_insert_with(Vec::new)
+                    .extend(values);
+
+                acc
+            });
+
+        let header_order = headers.keys().cloned().collect();
+
+        Ok(SignatureParams {
+            key_id,
+            algorithm,
+            headers,
+            header_order,
+            created: None,
+            expires: None,
+            base64: Base64Variant::default(),
+            strict_headers: false,
+        })
+    }
+
+    /// Only sign the named headers, in the order they are given, as with
+    /// `HttpSignature::with_signed_headers`
+    pub fn with_signed_headers(mut self, header_names: &[&str]) -> Result<Self, CreationError> {
+        let mut header_order = Vec::new();
+        let mut missing = Vec::new();
+
+        for name in header_names {
+            let name = name.to_lowercase();
+
+            if header_order.contains(&name) || missing.contains(&name) {
+                continue;
+            }
+
+            if self.headers.contains_key(&name) {
+                header_order.push(name);
+            } else {
+                missing.push(name);
+            }
+        }
+
+        if !missing.is_empty() {
+            return Err(CreationError::MissingHeaders(missing.join(", ")));
+        }
+
+        let headers: BTreeMap<_, _> = self.headers
+            .into_iter()
+            .filter(|&(ref key, _)| header_order.contains(key))
+            .collect();
+
+        self.headers = headers;
+        self.header_order = header_order;
+
+        Ok(self)
+    }
+
+    /// Create an `HttpSignature` with these parameters, which will be signed with `key`
+    pub fn with_key<T>(self, key: T) -> HttpSignature<T>
+    where
+        T: Read,
+    {
+        HttpSignature { params: self, key }
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub fn algorithm(&self) -> &SignatureAlgorithm {
+        &self.algorithm
+    }
+
+    pub fn headers(&self) -> &BTreeMap<String, Vec<String>> {
+        &self.headers
+    }
+
+    /// The names of the signed headers, in the order they appear in the `headers` parameter
+    pub fn signed_headers(&self) -> &[String] {
+        &self.header_order
+    }
+
+    pub fn created(&self) -> Option<u64> {
+        self.created
+    }
+
+    pub fn expires(&self) -> Option<u64> {
+        self.expires
+    }
+
+    /// Get the string that will be signed, without signing it
+    pub fn signing_string(&self) -> String {
+        self.signing_lines().1
+    }
+
+    /// The names and values of the headers that will be signed, in signing order, as with
+    /// `HttpSignature::header_pairs`
+    pub fn header_pairs(&self) -> Vec<(String, String)> {
+        let timestamps = self.created
+            .map(|created| (CREATED, created))
+            .into_iter()
+            .chain(self.expires.map(|expires| (EXPIRES, expires)))
+            .map(|(pseudo_header, timestamp)| (pseudo_header.to_owned(), timestamp.to_string()));
+
+        let headers = &self.headers;
+
+        timestamps
+            .chain(self.header_order.iter().filter_map(|header| {
+                headers.get(header).map(|values| {
+                    let values: Vec<String> =
+                        values.iter().map(|value| unfold_header_value(value)).collect();
+
+                    (header.clone(), values.join(", "))
+                })
+            }))
+            .collect()
+    }
+
+    /// Generate the Authorization Header, signing it with a `Signer`, such as an already parsed
+    /// `SigningKey`, whose key id and algorithm are used
+    pub fn authorization_header_using<S>(self, signer: &S) -> Result<String, CreationError>
+    where
+        S: Signer,
+    {
+        Ok(self.signature_using(signer)?.authorization())
+    }
+
+    /// Generate the Signature Header, signing it with a `Signer`, such as an already parsed
+    /// `SigningKey`, whose key id and algorithm are used
+    pub fn signature_header_using<S>(self, signer: &S) -> Result<String, CreationError>
+    where
+        S: Signer,
+    {
+        Ok(self.signature_using(signer)?.signature())
+    }
+
+    pub fn signature_using<S>(self, signer: &S) -> Result<Signature, CreationError>
+    where
+        S: Signer,
+    {
+        self.check_timestamps()?;
+        self.check_headers()?;
+
+        let (headers, signing_string) = self.signing_lines();
+
+        Signature::sign(
+            signer,
+            &signing_string,
+            headers,
+            self.created,
+            self.expires,
+            self.base64,
+        )
+    }
+
+    /// Build the signed header names and the signing string, in signing order
+    fn signing_lines(&self) -> (Vec<String>, String) {
+        let (header_keys, signing_vec): (Vec<_>, Vec<String>) = self.header_pairs()
+            .into_iter()
+            .map(|(header, value)| {
+                let line = format!("{}: {}", header, value);
+
+                (header, line)
+            })
+            .unzip();
+
+        (header_keys, signing_vec.join("\n"))
+    }
+
+    fn check_timestamps(&self) -> Result<(), CreationError> {
+        if let (Some(created), Some(expires)) = (self.created, self.expires) {
+            if expires < created {
+                return Err(CreationError::ExpiresBeforeCreated);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_headers(&self) -> Result<(), CreationError> {
+        let bound = self.created.is_some()
+            || self.header_order
+                .iter()
+                .any(|header| header == REQUEST_TARGET || header == DATE);
+
+        if !bound {
+            if self.strict_headers {
+                return Err(CreationError::WeakHeaders);
+            }
+
+            trace::weak_headers(&self.key_id);
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> fmt::Debug for HttpSignature<T>
+where
+    T: Read,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HttpSignature")
+            .field("params", &self.params)
+            .field("key", &"<redacted>")
+            .finish()
+    }
 }
 
 impl<T> HttpSignature<T>
@@ -51,7 +337,16 @@ where
 {
     /// Create a new HttpSignature from its components.
     ///
-    /// This method will Error if `headers` is empty.
+    /// This method will Error if `headers` is empty, or if `key_id` is empty or contains
+    /// characters that can't appear in the quoted `keyId` parameter, such as `"`.
+    ///
+    /// Header names are lowercased. Headers whose names differ only in case are treated as one
+    /// header, and all of their values are signed on a single line, joined by `", "`. Leading and
+    /// trailing whitespace is removed from each value before signing.
+    ///
+    /// The key may be anything implementing `Read`. Keys that are already loaded into memory can
+    /// be passed as a `&[u8]`, which avoids any I/O when signing, and doesn't need to be wrapped
+    /// in a `Cursor`.
     ///
     /// ### Example
     /// ```rust
@@ -79,28 +374,279 @@ where
         algorithm: SignatureAlgorithm,
         headers: BTreeMap<String, Vec<String>>,
     ) -> Result<Self, CreationError> {
-        if headers.is_empty() {
-            return Err(CreationError::NoHeaders);
+        Ok(SignatureParams::new(key_id, algorithm, headers)?.with_key(key))
+    }
+
+    /// Create an `HttpSignature` which may have no headers, for signatures that only sign their
+    /// `(created)` timestamp
+    fn with_headers(
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+        headers: BTreeMap<String, Vec<String>>,
+    ) -> Result<Self, CreationError> {
+        Ok(SignatureParams::with_headers(key_id, algorithm, headers)?.with_key(key))
+    }
+
+    /// Only sign the named headers, in the order they are given.
+    ///
+    /// Header names are matched case-insensitively. Names given more than once are only signed
+    /// once, at their first position. This method will Error if one of the named headers is not
+    /// present.
+    ///
+    /// ### Example
+    /// ```rust
+    /// # use std::fs::File;
+    /// # use std::collections::BTreeMap;
+    /// # use http_signatures::Error;
+    /// use http_signatures::{HttpSignature, SignatureAlgorithm, ShaSize, REQUEST_TARGET};
+    ///
+    /// # fn run() -> Result<(), Error> {
+    /// # let key_id = "tests/assets/public.der".into();
+    /// # let priv_key = File::open("tests/assets/private.der")?;
+    /// # let alg = SignatureAlgorithm::RSA(ShaSize::FiveTwelve);
+    /// let mut headers = BTreeMap::new();
+    /// headers.insert(REQUEST_TARGET.into(), vec!["get /".into()]);
+    /// headers.insert("Date".into(), vec!["Tue, 07 Jun 2014 20:51:35 GMT".into()]);
+    /// headers.insert("Accept".into(), vec!["application/json".into()]);
+    ///
+    /// let http_sig = HttpSignature::new(key_id, priv_key, alg, headers)?
+    ///     .with_signed_headers(&[REQUEST_TARGET, "date"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_signed_headers(self, header_names: &[&str]) -> Result<Self, CreationError> {
+        let HttpSignature { params, key } = self;
+
+        Ok(params.with_signed_headers(header_names)?.with_key(key))
+    }
+
+    /// Replace the value of the `(request-target)` pseudo-header
+    pub(crate) fn with_request_target(mut self, request_target: String) -> Self {
+        self.params.headers
+            .insert(REQUEST_TARGET.to_owned(), vec![request_target]);
+        self
+    }
+
+    /// Add a header with the given value if no header with that name is present. Since the header
+    /// order is reset, this must be called before `with_signed_headers`.
+    pub(crate) fn with_header_if_missing(mut self, name: &str, value: &str) -> Self {
+        let name = name.to_lowercase();
+
+        if !self.params.headers.contains_key(&name) {
+            self.params.headers.insert(name, vec![value.to_owned()]);
+            self.params.header_order = self.params.headers.keys().cloned().collect();
         }
 
-        Ok(HttpSignature {
-            key_id,
-            key,
-            algorithm,
-            headers,
-        })
+        self
+    }
+
+    /// Add the trailer fields of a chunked message, so they can be signed like headers.
+    ///
+    /// Trailers arrive after the body, so a signature covering them can only be sent once the
+    /// body has been written, for example as a trailer itself. Trailer names are lowercased, and
+    /// the values of a trailer sharing its name with a header are signed after the header's
+    /// values.
+    ///
+    /// Without a selection, every header and trailer is signed in alphabetical order, and
+    /// `with_signed_headers` can then choose the trailers to sign. After `with_signed_headers`,
+    /// the selection is kept, and trailers it doesn't name are signed after it.
+    ///
+    /// ### Example
+    /// ```rust
+    /// # use std::collections::BTreeMap;
+    /// # use http_signatures::Error;
+    /// use http_signatures::{HttpSignature, SignatureAlgorithm, ShaSize, REQUEST_TARGET};
+    ///
+    /// # fn run() -> Result<(), Error> {
+    /// # let alg = SignatureAlgorithm::HMAC(ShaSize::TwoFiftySix);
+    /// let mut headers = BTreeMap::new();
+    /// headers.insert(REQUEST_TARGET.into(), vec!["post /upload".into()]);
+    ///
+    /// let http_sig = HttpSignature::new("hmac-key-1".into(), &b"secret"[..], alg, headers)?
+    ///     .with_trailers(vec![("X-Checksum", "8843d7f92416211d")])
+    ///     .with_signed_headers(&[REQUEST_TARGET, "x-checksum"])?;
+    ///
+    /// assert_eq!(
+    ///     http_sig.signing_string(),
+    ///     "(request-target): post /upload\nx-checksum: 8843d7f92416211d"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// #     run().unwrap();
+    /// # }
+    /// ```
+    pub fn with_trailers<'a, I>(mut self, trailers: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let selected = self.params
+            .header_order
+            .iter()
+            .ne(self.params.headers.keys());
+
+        for (name, value) in trailers {
+            let name = name.to_lowercase();
+
+            if selected && !self.params.header_order.contains(&name) {
+                self.params.header_order.push(name.clone());
+            }
+
+            self.params.headers
+                .entry(name)
+                .or_insert_with(Vec::new)
+                .push(value.to_owned());
+        }
+
+        if !selected {
+            self.params.header_order = self.params.headers.keys().cloned().collect();
+        }
+
+        self
+    }
+
+    /// Sign the `(created)` pseudo-header, set to the given Unix timestamp.
+    ///
+    /// The timestamp is also added to the header as the `created` parameter.
+    pub fn with_created(mut self, created: u64) -> Self {
+        self.params.created = Some(created);
+        self
+    }
+
+    /// Sign the `(expires)` pseudo-header, set to the given Unix timestamp.
+    ///
+    /// The timestamp is also added to the header as the `expires` parameter. Creating the
+    /// signature will Error if this is earlier than the `created` timestamp.
+    ///
+    /// Timestamps are whole seconds, in both this format and RFC 9421, where they are Integers.
+    pub fn with_expires(mut self, expires: u64) -> Self {
+        self.params.expires = Some(expires);
+        self
+    }
+
+    /// Encode the signature with the given base64 variant, instead of standard padded base64.
+    ///
+    /// The specification requires standard padded base64, so this should only be used for peers
+    /// that expect something else.
+    pub fn with_base64_variant(mut self, variant: Base64Variant) -> Self {
+        self.params.base64 = variant;
+        self
+    }
+
+    /// Fail to sign, with `CreationError::WeakHeaders`, unless `(request-target)`, `date` or
+    /// `(created)` is signed.
+    ///
+    /// A signature over none of these can be replayed on any request with the same headers, so
+    /// it is usually a mistake, such as selecting the wrong headers. Without this, such
+    /// signatures are still created, and a warning is emitted when the `tracing` feature is
+    /// enabled.
+    pub fn with_strict_headers(mut self) -> Self {
+        self.params.strict_headers = true;
+        self
     }
 
     pub fn key_id(&self) -> &str {
-        &self.key_id
+        self.params().key_id()
     }
 
     pub fn algorithm(&self) -> &SignatureAlgorithm {
-        &self.algorithm
+        self.params().algorithm()
     }
 
     pub fn headers(&self) -> &BTreeMap<String, Vec<String>> {
-        &self.headers
+        self.params().headers()
+    }
+
+    /// The names of the signed headers, in the order they appear in the `headers` parameter.
+    ///
+    /// When they are set, the `(created)` and `(expires)` pseudo-headers are signed before these.
+    pub fn signed_headers(&self) -> &[String] {
+        self.params().signed_headers()
+    }
+
+    pub fn created(&self) -> Option<u64> {
+        self.params().created()
+    }
+
+    pub fn expires(&self) -> Option<u64> {
+        self.params().expires()
+    }
+
+    /// Everything about the signature but the key
+    pub fn params(&self) -> &SignatureParams {
+        &self.params
+    }
+
+    /// Drop the key, keeping everything else about the signature
+    pub fn into_params(self) -> SignatureParams {
+        self.params
+    }
+
+    /// Get the string that will be signed, without signing it.
+    ///
+    /// This is meant for diagnostics: when a peer fails to verify a signature, comparing the
+    /// signing string it computed with this one usually shows which header differs.
+    ///
+    /// ### Example
+    /// ```rust
+    /// # use std::collections::BTreeMap;
+    /// # use http_signatures::Error;
+    /// use http_signatures::{HttpSignature, SignatureAlgorithm, ShaSize, REQUEST_TARGET};
+    ///
+    /// # fn run() -> Result<(), Error> {
+    /// let mut headers = BTreeMap::new();
+    /// headers.insert(REQUEST_TARGET.into(), vec!["get /".into()]);
+    /// headers.insert("Date".into(), vec!["Tue, 07 Jun 2014 20:51:35 GMT".into()]);
+    ///
+    /// let alg = SignatureAlgorithm::HMAC(ShaSize::TwoFiftySix);
+    /// let http_sig = HttpSignature::new("hmac-key-1".into(), &b"secret"[..], alg, headers)?;
+    ///
+    /// assert_eq!(
+    ///     http_sig.signing_string(),
+    ///     "(request-target): get /\ndate: Tue, 07 Jun 2014 20:51:35 GMT"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn signing_string(&self) -> String {
+        self.params.signing_string()
+    }
+
+    /// The names and values of the headers that will be signed, in signing order, without
+    /// signing them.
+    ///
+    /// Values are canonicalized as they are in the signing string: repeated headers are joined
+    /// with `, ` and folded lines are unfolded. The `(created)` and `(expires)` pseudo-headers
+    /// come first when they are set. This lets tests check exactly what is signed.
+    ///
+    /// ### Example
+    /// ```rust
+    /// # use std::collections::BTreeMap;
+    /// # use http_signatures::Error;
+    /// use http_signatures::{HttpSignature, SignatureAlgorithm, ShaSize};
+    ///
+    /// # fn run() -> Result<(), Error> {
+    /// let mut headers = BTreeMap::new();
+    /// headers.insert("Accept".into(), vec!["text/html".into(), "application/json".into()]);
+    ///
+    /// let alg = SignatureAlgorithm::HMAC(ShaSize::TwoFiftySix);
+    /// let http_sig = HttpSignature::new("hmac-key-1".into(), &b"secret"[..], alg, headers)?
+    ///     .with_created(1402170695);
+    ///
+    /// assert_eq!(
+    ///     http_sig.header_pairs(),
+    ///     vec![
+    ///         ("(created)".to_owned(), "1402170695".to_owned()),
+    ///         ("accept".to_owned(), "text/html, application/json".to_owned()),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn header_pairs(&self) -> Vec<(String, String)> {
+        self.params.header_pairs()
     }
 
     /// Generate the Authorization Header from the `HttpSignature`
@@ -157,10 +703,201 @@ where
         Ok(self.signature()?.signature())
     }
 
+    /// Generate the Authorization Header, along with the signing string that was signed to create
+    /// it.
+    ///
+    /// Keeping the signing string lets auditors check the signature offline, without rebuilding
+    /// the request it was made for.
+    ///
+    /// ### Example
+    /// ```rust
+    /// # use std::collections::BTreeMap;
+    /// # use http_signatures::Error;
+    /// use http_signatures::{HttpSignature, SignatureAlgorithm, ShaSize, REQUEST_TARGET};
+    ///
+    /// # fn run() -> Result<(), Error> {
+    /// let mut headers = BTreeMap::new();
+    /// headers.insert(REQUEST_TARGET.into(), vec!["get /".into()]);
+    ///
+    /// let alg = SignatureAlgorithm::HMAC(ShaSize::TwoFiftySix);
+    /// let http_sig = HttpSignature::new("hmac-key-1".into(), &b"secret"[..], alg, headers)?;
+    ///
+    /// let (auth_header, signing_string) = http_sig.authorization_header_with_signing_string()?;
+    /// assert_eq!(signing_string, "(request-target): get /");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn authorization_header_with_signing_string(
+        self,
+    ) -> Result<(String, String), CreationError> {
+        let signature = self.signature()?;
+        let signing_string = signature.signing_string.clone();
+
+        Ok((signature.authorization(), signing_string))
+    }
+
+    /// Generate the Signature Header, along with the signing string that was signed to create it.
+    pub fn signature_header_with_signing_string(self) -> Result<(String, String), CreationError> {
+        let signature = self.signature()?;
+        let signing_string = signature.signing_string.clone();
+
+        Ok((signature.signature(), signing_string))
+    }
+
+    /// Generate only the base64 encoded signature of the signing string, without the `keyId`,
+    /// `algorithm`, and `headers` parameters around it.
+    ///
+    /// This is useful for embedding the signature in a custom header or protocol.
+    pub fn signature_value(self) -> Result<String, CreationError> {
+        Ok(self.signature()?.sig)
+    }
+
     pub fn signature(self) -> Result<Signature, CreationError> {
+        self.params.check_timestamps()?;
+        self.params.check_headers()?;
+
         let signing_string: SigningString<T> = self.into();
         signing_string.try_into()
     }
+
+    /// Generate the Authorization Header, signing it with a `Signer`, such as an already parsed
+    /// `SigningKey`.
+    ///
+    /// The key id and algorithm are taken from `signer`, and the key this `HttpSignature` was
+    /// created with is never read. This avoids parsing the key, and any I/O, for each request.
+    /// Signatures that are only ever signed this way can be built without a key, as a
+    /// `SignatureParams`.
+    pub fn authorization_header_using<S>(self, signer: &S) -> Result<String, CreationError>
+    where
+        S: Signer,
+    {
+        self.params.authorization_header_using(signer)
+    }
+
+    /// Generate the Signature Header, signing it with a `Signer`, such as an already parsed
+    /// `SigningKey`.
+    ///
+    /// The key id and algorithm are taken from `signer`, and the key this `HttpSignature` was
+    /// created with is never read. This avoids parsing the key, and any I/O, for each request.
+    pub fn signature_header_using<S>(self, signer: &S) -> Result<String, CreationError>
+    where
+        S: Signer,
+    {
+        self.params.signature_header_using(signer)
+    }
+
+    pub fn signature_using<S>(self, signer: &S) -> Result<Signature, CreationError>
+    where
+        S: Signer,
+    {
+        self.params.signature_using(signer)
+    }
+}
+
+/// The `HttpSignatureBuilder` struct builds an `HttpSignature` one header at a time, for when the
+/// headers don't come from a request type this crate supports.
+///
+/// ### Example
+/// ```rust
+/// # use std::fs::File;
+/// # use http_signatures::Error;
+/// use http_signatures::{HttpSignatureBuilder, SignatureAlgorithm, ShaSize, REQUEST_TARGET};
+///
+/// # fn run() -> Result<(), Error> {
+/// let priv_key = File::open("tests/assets/private.der")?;
+///
+/// let auth_header = HttpSignatureBuilder::new()
+///     .key_id("rsa-key-1")
+///     .algorithm(SignatureAlgorithm::RSA(ShaSize::FiveTwelve))
+///     .header(REQUEST_TARGET, "get /")
+///     .header("Date", "Tue, 07 Jun 2014 20:51:35 GMT")
+///     .created(1402170695)
+///     .build(priv_key)?
+///     .authorization_header()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HttpSignatureBuilder {
+    key_id: String,
+    algorithm: Option<SignatureAlgorithm>,
+    headers: BTreeMap<String, Vec<String>>,
+    created: Option<u64>,
+    expires: Option<u64>,
+}
+
+impl HttpSignatureBuilder {
+    pub fn new() -> Self {
+        HttpSignatureBuilder::default()
+    }
+
+    /// Set the keyId field of the header
+    pub fn key_id<S>(mut self, key_id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.key_id = key_id.into();
+        self
+    }
+
+    /// Set the algorithm used to sign the request
+    pub fn algorithm(mut self, algorithm: SignatureAlgorithm) -> Self {
+        self.algorithm = Some(algorithm);
+        self
+    }
+
+    /// Add a header to sign. Adding a header more than once signs all of its values, in the order
+    /// they were added.
+    pub fn header<S>(mut self, name: &str, value: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.headers
+            .entry(name.to_lowercase())
+            .or_insert_with(Vec::new)
+            .push(value.into());
+        self
+    }
+
+    /// Sign the `(created)` pseudo-header, set to the given Unix timestamp
+    pub fn created(mut self, created: u64) -> Self {
+        self.created = Some(created);
+        self
+    }
+
+    /// Sign the `(expires)` pseudo-header, set to the given Unix timestamp
+    pub fn expires(mut self, expires: u64) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    /// Create the `HttpSignature`, which will be signed with `key`.
+    ///
+    /// This method will Error if the key id is empty, if no algorithm was set, or if no headers
+    /// were added. When only the `created` timestamp was set, as later revisions of the draft
+    /// allow, just `(created)` is signed, and the header still lists it explicitly as
+    /// `headers="(created)"`.
+    pub fn build<T>(self, key: T) -> Result<HttpSignature<T>, CreationError>
+    where
+        T: Read,
+    {
+        if self.key_id.is_empty() {
+            return Err(CreationError::NoKeyId);
+        }
+
+        let algorithm = self.algorithm.ok_or(CreationError::NoAlgorithm)?;
+
+        if self.headers.is_empty() && self.created.is_none() {
+            return Err(CreationError::NoHeaders);
+        }
+
+        let mut http_signature =
+            HttpSignature::with_headers(self.key_id, key, algorithm, self.headers)?;
+        http_signature.params.created = self.created;
+        http_signature.params.expires = self.expires;
+
+        Ok(http_signature)
+    }
 }
 
 /// A default implementation of `AsHttpSignature` for `HttpSignature`.
@@ -172,12 +909,7 @@ where
     T: Read + Clone,
 {
     fn as_http_signature(&self, _: String, _: T, _: SignatureAlgorithm) -> Result<Self, Error> {
-        Ok(HttpSignature {
-            key_id: self.key_id.clone(),
-            key: self.key.clone(),
-            algorithm: self.algorithm.clone(),
-            headers: self.headers.clone(),
-        })
+        Ok(self.clone())
     }
 }
 
@@ -189,38 +921,51 @@ where
 ///
 /// This struct does not have public fields, and does not have a constructor since it should only
 /// be used as an intermediate point from `HttpSignature<T>` to the signed string.
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct SigningString<T> {
     key_id: String,
     key: T,
     headers: Vec<String>,
     algorithm: SignatureAlgorithm,
+    created: Option<u64>,
+    expires: Option<u64>,
+    base64: Base64Variant,
     // The plaintext string used to sign the request
     pub signing_string: String,
 }
 
+impl<T> fmt::Debug for SigningString<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SigningString")
+            .field("key_id", &self.key_id)
+            .field("key", &"<redacted>")
+            .field("headers", &self.headers)
+            .field("algorithm", &self.algorithm)
+            .field("created", &self.created)
+            .field("expires", &self.expires)
+            .field("base64", &self.base64)
+            .field("signing_string", &self.signing_string)
+            .finish()
+    }
+}
+
 impl<T> From<HttpSignature<T>> for SigningString<T>
 where
     T: Read,
 {
     fn from(http_signature: HttpSignature<T>) -> Self {
-        let (header_keys, signing_vec): (Vec<_>, Vec<_>) = http_signature
-            .headers
-            .iter()
-            .map(|(header, values)| {
-                (
-                    header.to_lowercase(),
-                    format!("{}: {}", header.to_lowercase(), values.join(", ")),
-                )
-            })
-            .unzip();
+        let (header_keys, signing_string) = http_signature.params.signing_lines();
+        let HttpSignature { params, key } = http_signature;
 
         SigningString {
-            key_id: http_signature.key_id,
-            key: http_signature.key,
+            key_id: params.key_id,
+            key,
             headers: header_keys,
-            algorithm: http_signature.algorithm,
-            signing_string: signing_vec.join("\n"),
+            algorithm: params.algorithm,
+            created: params.created,
+            expires: params.expires,
+            base64: params.base64,
+            signing_string,
         }
     }
 }
@@ -233,9 +978,12 @@ where
 #[derive(Clone, Debug)]
 pub struct Signature {
     sig: String,
+    signing_string: String,
     key_id: String,
     headers: Vec<String>,
     algorithm: SignatureAlgorithm,
+    created: Option<u64>,
+    expires: Option<u64>,
 }
 
 impl Signature {
@@ -249,69 +997,58 @@ impl Signature {
         self.header()
     }
 
+    /// Get the signing string that was signed to create this signature.
+    pub fn signing_string(&self) -> &str {
+        &self.signing_string
+    }
+
+    fn sign<S>(
+        signer: &S,
+        signing_string: &str,
+        headers: Vec<String>,
+        created: Option<u64>,
+        expires: Option<u64>,
+        base64: Base64Variant,
+    ) -> Result<Self, CreationError>
+    where
+        S: Signer,
+    {
+        check_key_id(signer.key_id())?;
+
+        let span = trace::sign_span(signer.key_id(), signer.algorithm());
+        let _guard = span.enter();
+        let sig = signer.sign(signing_string.as_bytes());
+        trace::creation(&sig, "signing");
+
+        Ok(Signature {
+            sig: base64.encode(&sig?),
+            signing_string: signing_string.to_owned(),
+            key_id: signer.key_id().to_owned(),
+            headers,
+            algorithm: signer.algorithm().clone(),
+            created,
+            expires,
+        })
+    }
+
     fn header(self) -> String {
         let alg: &str = self.algorithm.into();
 
+        let timestamps = self.created
+            .map(|created| format!("created={},", created))
+            .into_iter()
+            .chain(self.expires.map(|expires| format!("expires={},", expires)))
+            .collect::<String>();
+
         format!(
-            "Signature keyId=\"{}\",algorithm=\"{}\",headers=\"{}\",signature=\"{}\"",
+            "keyId=\"{}\",algorithm=\"{}\",{}headers=\"{}\",signature=\"{}\"",
             self.key_id,
             alg,
+            timestamps,
             self.headers.join(" "),
             self.sig,
         )
     }
-
-    fn rsa<T>(mut key: T, size: &ShaSize, signing_string: &[u8]) -> Result<String, CreationError>
-    where
-        T: Read,
-    {
-        let mut private_key_der = Vec::new();
-        key.read_to_end(&mut private_key_der)?;
-        let private_key_der = Input::from(&private_key_der);
-
-        let key_pair = signature::RSAKeyPair::from_der(private_key_der)
-            .map_err(|_| CreationError::BadPrivateKey)?;
-        let key_pair = Arc::new(key_pair);
-
-        let mut signing_state =
-            signature::RSASigningState::new(key_pair).map_err(|_| CreationError::SigningError)?;
-
-        let rng = rand::SystemRandom::new();
-        let mut signature = vec![0; signing_state.key_pair().public_modulus_len()];
-        signing_state
-            .sign(
-                match *size {
-                    ShaSize::TwoFiftySix => &signature::RSA_PKCS1_SHA256,
-                    ShaSize::ThreeEightyFour => &signature::RSA_PKCS1_SHA384,
-                    ShaSize::FiveTwelve => &signature::RSA_PKCS1_SHA512,
-                },
-                &rng,
-                signing_string,
-                signature.as_mut_slice(),
-            )
-            .map_err(|_| CreationError::SigningError)?;
-
-        Ok(encode(signature.as_slice()))
-    }
-
-    fn hmac<T>(mut key: T, size: &ShaSize, signing_string: &[u8]) -> Result<String, CreationError>
-    where
-        T: Read,
-    {
-        let mut hmac_key = Vec::new();
-        key.read_to_end(&mut hmac_key)?;
-        let hmac_key = hmac::SigningKey::new(
-            match *size {
-                ShaSize::TwoFiftySix => &digest::SHA256,
-                ShaSize::ThreeEightyFour => &digest::SHA384,
-                ShaSize::FiveTwelve => &digest::SHA512,
-            },
-            &hmac_key,
-        );
-        let signature = hmac::sign(&hmac_key, signing_string);
-
-        Ok(encode(signature.as_ref()))
-    }
 }
 
 impl<T> TryFrom<SigningString<T>> for Signature
@@ -323,27 +1060,63 @@ where
     /// Attempt to sign the signing_string. If signing fails, a `Signature` will not be created and
     /// an `Error` will be returned.
     fn try_from(signing_string: SigningString<T>) -> Result<Self, Self::Error> {
-        Ok(match signing_string.algorithm {
-            SignatureAlgorithm::RSA(size) => Signature {
-                sig: Signature::rsa(
-                    signing_string.key,
-                    &size,
-                    signing_string.signing_string.as_ref(),
-                )?,
-                key_id: signing_string.key_id,
-                headers: signing_string.headers,
-                algorithm: SignatureAlgorithm::RSA(size),
-            },
-            SignatureAlgorithm::HMAC(size) => Signature {
-                sig: Signature::hmac(
-                    signing_string.key,
-                    &size,
-                    signing_string.signing_string.as_ref(),
-                )?,
-                key_id: signing_string.key_id,
-                headers: signing_string.headers,
-                algorithm: SignatureAlgorithm::HMAC(size),
-            },
-        })
+        let key = SigningKey::new(
+            signing_string.key_id,
+            signing_string.key,
+            signing_string.algorithm,
+        )?;
+
+        Signature::sign(
+            &key,
+            &signing_string.signing_string,
+            signing_string.headers,
+            signing_string.created,
+            signing_string.expires,
+            signing_string.base64,
+        )
     }
 }
+
+/// Sign a signing string that was built elsewhere, returning the base64 encoded signature.
+///
+/// This skips building the signing string from a request, which is useful for creating test
+/// vectors and for checking signatures by hand against another implementation.
+///
+/// ### Example
+/// ```rust
+/// # use std::io::Cursor;
+/// use http_signatures::{sign_signing_string, ShaSize, SignatureAlgorithm};
+///
+/// let signature = sign_signing_string(
+///     "(request-target): get /foo\ndate: Tue, 07 Jun 2014 20:51:35 GMT",
+///     Cursor::new(b"shared secret".to_vec()),
+///     SignatureAlgorithm::HMAC(ShaSize::TwoFiftySix),
+/// ).unwrap();
+/// ```
+pub fn sign_signing_string<T>(
+    signing_string: &str,
+    key: T,
+    algorithm: SignatureAlgorithm,
+) -> Result<String, Error>
+where
+    T: Read,
+{
+    let key = SigningKey::without_key_id(key, algorithm)?;
+
+    Ok(Base64Variant::Standard.encode(&key.sign(signing_string)?))
+}
+
+/// Sign arbitrary `data` with the same cryptography that signs HTTP Signatures, returning the raw
+/// signature bytes.
+///
+/// No signing string is built, so this can sign payloads that aren't HTTP requests, such as
+/// webhook bodies. The key is read as in `HttpSignature::new`. Signatures are checked with
+/// `verify_bytes`.
+pub fn sign_bytes<T>(data: &[u8], key: T, algorithm: SignatureAlgorithm) -> Result<Vec<u8>, Error>
+where
+    T: Read,
+{
+    let key = SigningKey::without_key_id(key, algorithm)?;
+
+    Ok(Signer::sign(&key, data)?)
+}
@@ -0,0 +1,344 @@
+// This file is part of HTTP Signatures
+
+// HTTP Signatures is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// HTTP Signatures is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module defines the `Digest` type for creating Digest headers from request bodies.
+
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::str::FromStr;
+
+use base64::{decode, encode};
+use ring::{constant_time, digest};
+
+use error::{DecodeError, VerificationError};
+use super::{Base64Variant, ShaSize};
+
+/// How much of a body `Digest::from_reader` reads at a time
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// The `Digest` struct holds the hash of a request body.
+///
+/// Its `Display` implementation produces the value of a Digest header, such as
+/// `SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=`. Add the Digest header to the request
+/// before signing it, so that the `digest` header is included in the signature.
+///
+/// ### Example
+/// ```rust
+/// use http_signatures::{Digest, ShaSize};
+///
+/// let digest = Digest::from_body(br#"{"hello": "world"}"#, ShaSize::TwoFiftySix);
+///
+/// assert_eq!(
+///     digest.to_string(),
+///     "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE="
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Digest {
+    size: ShaSize,
+    hash: Vec<u8>,
+}
+
+impl Digest {
+    /// Hash the given body. An empty body still produces a valid digest.
+    pub fn from_body(body: &[u8], size: ShaSize) -> Self {
+        let hash = digest::digest(algorithm(&size), body).as_ref().to_vec();
+
+        Digest { size, hash }
+    }
+
+    /// Hash a body by reading it in chunks, so it is never held in memory all at once.
+    ///
+    /// The body must be seekable: once it has been hashed, it is seeked back to the position it
+    /// was read from, so the same bytes can then be sent as the body of the request. Bodies that
+    /// can't seek, such as sockets or pipes, have to be written to a temporary file first, or
+    /// teed into one while they are hashed.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use std::io::{Cursor, Read};
+    /// use http_signatures::{Digest, ShaSize};
+    ///
+    /// # fn run() -> Result<(), ::std::io::Error> {
+    /// let mut body = Cursor::new(br#"{"hello": "world"}"#.to_vec());
+    /// let digest = Digest::from_reader(&mut body, ShaSize::TwoFiftySix)?;
+    ///
+    /// assert_eq!(
+    ///     digest.to_string(),
+    ///     "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE="
+    /// );
+    ///
+    /// // The body can still be sent
+    /// let mut sent = String::new();
+    /// body.read_to_string(&mut sent)?;
+    /// assert_eq!(sent, r#"{"hello": "world"}"#);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_reader<R: Read + Seek>(body: &mut R, size: ShaSize) -> io::Result<Self> {
+        let start = body.seek(SeekFrom::Current(0))?;
+        let mut context = digest::Context::new(algorithm(&size));
+        let mut buf = [0; CHUNK_SIZE];
+
+        loop {
+            match body.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => context.update(&buf[..read]),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        body.seek(SeekFrom::Start(start))?;
+
+        let hash = context.finish().as_ref().to_vec();
+
+        Ok(Digest { size, hash })
+    }
+
+    /// Check a Digest header against the body it should describe.
+    ///
+    /// The header may hold several comma-separated digests, each of which is checked. Digests
+    /// using unsupported algorithms are ignored, but at least one digest must use a supported
+    /// algorithm. Malformed headers fail with `DecodeError::InvalidDigest`, and headers that
+    /// don't match the body fail with `VerificationError::DigestMismatch`.
+    pub fn verify_header(header: &str, body: &[u8]) -> Result<(), VerificationError> {
+        let mut checked = false;
+
+        for entry in header.split(',') {
+            let eq_index = entry.find('=').ok_or(DecodeError::InvalidDigest)?;
+
+            if size_from_token(&entry[..eq_index]).is_none() {
+                continue;
+            }
+
+            let digest: Digest = entry.parse()?;
+            digest.verify(body)?;
+            checked = true;
+        }
+
+        if checked {
+            Ok(())
+        } else {
+            Err(DecodeError::InvalidDigest.into())
+        }
+    }
+
+    /// Check that this digest matches the given body.
+    ///
+    /// The hashes are compared in constant time, so the comparison doesn't reveal how much of the
+    /// digest matched.
+    pub fn verify(&self, body: &[u8]) -> Result<(), VerificationError> {
+        let body_digest = Digest::from_body(body, self.size.clone());
+
+        constant_time::verify_slices_are_equal(&body_digest.hash, &self.hash)
+            .map_err(|_| VerificationError::DigestMismatch)
+    }
+
+    /// Get the value of a Digest header, with the hash encoded with the given base64 variant.
+    ///
+    /// The `Display` implementation uses standard padded base64, as the specification requires.
+    pub fn header_value(&self, variant: Base64Variant) -> String {
+        let prefix = match self.size {
+            ShaSize::TwoFiftySix => "SHA-256",
+            ShaSize::ThreeEightyFour => "SHA-384",
+            ShaSize::FiveTwelve => "SHA-512",
+        };
+
+        format!("{}={}", prefix, variant.encode(&self.hash))
+    }
+
+    /// Get the value of an RFC 9530 Content-Digest header, such as
+    /// `sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:`.
+    ///
+    /// The hash is a structured-field byte sequence, so it is wrapped in colons. RFC 9530 only
+    /// registers `sha-256` and `sha-512`, so SHA-384 digests return `None`.
+    pub fn content_digest_value(&self) -> Option<String> {
+        let prefix = match self.size {
+            ShaSize::TwoFiftySix => "sha-256",
+            ShaSize::ThreeEightyFour => return None,
+            ShaSize::FiveTwelve => "sha-512",
+        };
+
+        Some(format!("{}=:{}:", prefix, encode(&self.hash)))
+    }
+
+    pub fn size(&self) -> &ShaSize {
+        &self.size
+    }
+
+    pub fn hash(&self) -> &[u8] {
+        &self.hash
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.header_value(Base64Variant::Standard))
+    }
+}
+
+/// Parse a single `SHA-256=...` style digest, matching the algorithm case-insensitively
+impl FromStr for Digest {
+    type Err = DecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let eq_index = s.find('=').ok_or(DecodeError::InvalidDigest)?;
+        let (algorithm, value) = s.split_at(eq_index);
+
+        let size = size_from_token(algorithm).ok_or(DecodeError::InvalidDigest)?;
+
+        let hash = decode(value[1..].trim()).map_err(|_| DecodeError::InvalidDigest)?;
+
+        Ok(Digest { size, hash })
+    }
+}
+
+fn algorithm(size: &ShaSize) -> &'static digest::Algorithm {
+    match *size {
+        ShaSize::TwoFiftySix => &digest::SHA256,
+        ShaSize::ThreeEightyFour => &digest::SHA384,
+        ShaSize::FiveTwelve => &digest::SHA512,
+    }
+}
+
+fn size_from_token(token: &str) -> Option<ShaSize> {
+    match token.trim().to_uppercase().as_str() {
+        "SHA-256" => Some(ShaSize::TwoFiftySix),
+        "SHA-384" => Some(ShaSize::ThreeEightyFour),
+        "SHA-512" => Some(ShaSize::FiveTwelve),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read, Seek, SeekFrom};
+
+    use super::Digest;
+    use error::{DecodeError, VerificationError};
+    use Base64Variant;
+    use ShaSize;
+
+    #[test]
+    fn sha_256_digest() {
+        let digest = Digest::from_body(br#"{"hello": "world"}"#, ShaSize::TwoFiftySix);
+
+        assert_eq!(
+            digest.to_string(),
+            "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE="
+        );
+    }
+
+    #[test]
+    fn empty_body_digest() {
+        let digest = Digest::from_body(b"", ShaSize::TwoFiftySix);
+
+        assert_eq!(
+            digest.to_string(),
+            "SHA-256=47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="
+        );
+    }
+
+    #[test]
+    fn digest_base64_variants() {
+        let digest = Digest::from_body(b"", ShaSize::TwoFiftySix);
+
+        assert_eq!(
+            digest.header_value(Base64Variant::Standard),
+            digest.to_string()
+        );
+        assert_eq!(
+            digest.header_value(Base64Variant::UrlSafeNoPad),
+            "SHA-256=47DEQpj8HBSa-_TImW-5JCeuQeRkm5NMpJWZG3hSuFU"
+        );
+    }
+
+    #[test]
+    fn sha_512_digest_prefix() {
+        let digest = Digest::from_body(b"", ShaSize::FiveTwelve);
+
+        assert!(digest.to_string().starts_with("SHA-512="));
+        assert_eq!(digest.hash().len(), 64);
+    }
+
+    #[test]
+    fn content_digest_values() {
+        let body = br#"{"hello": "world"}"#;
+
+        assert_eq!(
+            Digest::from_body(body, ShaSize::TwoFiftySix).content_digest_value(),
+            Some("sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:".to_owned())
+        );
+        assert_eq!(
+            Digest::from_body(body, ShaSize::FiveTwelve).content_digest_value(),
+            Some(format!(
+                "sha-512=:{}:",
+                concat!(
+                    "WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrI",
+                    "iYllu7BNNyealdVLvRwEmTHWXvJwew=="
+                )
+            ))
+        );
+        assert_eq!(
+            Digest::from_body(body, ShaSize::ThreeEightyFour).content_digest_value(),
+            None
+        );
+    }
+
+    #[test]
+    fn reader_digest_matches_body_digest() {
+        let body = vec![7; 20 * 1024 + 3];
+        let mut reader = Cursor::new(body.clone());
+        reader.seek(SeekFrom::Start(3)).unwrap();
+
+        let digest = Digest::from_reader(&mut reader, ShaSize::FiveTwelve).unwrap();
+
+        assert_eq!(
+            digest.to_string(),
+            Digest::from_body(&body[3..], ShaSize::FiveTwelve).to_string()
+        );
+
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, &body[3..]);
+    }
+
+    #[test]
+    fn verify_header_accepts_any_casing() {
+        let body = br#"{"hello": "world"}"#;
+
+        Digest::verify_header("sha-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=", body)
+            .unwrap();
+    }
+
+    #[test]
+    fn verify_header_detects_mismatch() {
+        let body = br#"{"hello": "moon"}"#;
+
+        match Digest::verify_header("SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=", body) {
+            Err(VerificationError::DigestMismatch) => (),
+            res => panic!("Expected DigestMismatch, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn verify_header_detects_malformed_header() {
+        match Digest::verify_header("SHA-256", b"") {
+            Err(VerificationError::Decode(DecodeError::InvalidDigest)) => (),
+            res => panic!("Expected InvalidDigest, got {:?}", res),
+        }
+    }
+}
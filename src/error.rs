@@ -14,6 +14,33 @@
 // along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
 
 //! This module defines the Error types for `http_signatures`.
+//!
+//! Each error is an enum, so callers can tell failures apart. When verifying a request, a missing
+//! header is `VerificationError::HeaderNotPresent`, a header that can't be decoded (including an
+//! unknown algorithm or a signature that isn't base64) is `VerificationError::Decode`, a key that
+//! can't be used is `VerificationError::GetKey`, `ReadKey` or `BadPublicKey`, and a signature that
+//! doesn't match is `VerificationError::BadSignature`.
+//!
+//! Errors that wrap another error return it from `source`, so error reporters following the
+//! chain reach the root cause, such as the `io::Error` behind `CreationError::IO`.
+//!
+//! ### Example
+//! ```rust
+//! use http_signatures::{DecodeError, Error, VerificationError};
+//!
+//! fn status_code(error: &Error) -> u16 {
+//!     match *error {
+//!         Error::Verification(VerificationError::HeaderNotPresent) => 401,
+//!         Error::Verification(VerificationError::Decode(DecodeError::InvalidAlgorithm(_))) => 400,
+//!         Error::Verification(VerificationError::Decode(_)) => 400,
+//!         Error::Verification(VerificationError::BadSignature) => 403,
+//!         _ => 500,
+//!     }
+//! }
+//!
+//! let error = Error::from(VerificationError::BadSignature);
+//! assert_eq!(status_code(&error), 403);
+//! ```
 
 use std::error::Error as StdError;
 use std::fmt;
@@ -31,6 +58,25 @@ pub enum Error {
     Creation(CreationError),
 }
 
+impl Error {
+    /// The comma-separated names of the headers that were missing, if signing or verifying
+    /// failed because headers were missing from the request
+    ///
+    /// This is useful when debugging requests rejected by another implementation, which often
+    /// requires a header such as `date` that the request doesn't have.
+    ///
+    /// There is no separate `Error::MissingHeader` variant. Missing headers are still reported as
+    /// `CreationError::MissingHeaders` and `VerificationError::MissingHeaders`, so code that
+    /// matches on those keeps working, and this method reads the names from either.
+    pub fn missing_headers(&self) -> Option<&str> {
+        match *self {
+            Error::Creation(CreationError::MissingHeaders(ref mh)) => Some(mh),
+            Error::Verification(VerificationError::MissingHeaders(ref mh)) => Some(mh),
+            _ => None,
+        }
+    }
+}
+
 impl From<IoError> for Error {
     fn from(e: IoError) -> Self {
         Error::IO(e)
@@ -81,6 +127,14 @@ impl StdError for Error {
             Error::Creation(ref ce) => Some(ce),
         }
     }
+
+    fn source(&self) -> Option<&(StdError + 'static)> {
+        match *self {
+            Error::IO(ref ie) => Some(ie),
+            Error::Verification(ref ve) => Some(ve),
+            Error::Creation(ref ce) => Some(ce),
+        }
+    }
 }
 
 /// When creating a signature doesn't work
@@ -90,10 +144,37 @@ pub enum CreationError {
     IO(IoError),
     /// Headers must be provided to sign a request
     NoHeaders,
+    /// A key id must be provided to sign a request
+    NoKeyId,
+    /// The key id is empty, or contains characters that can't appear in the quoted `keyId`
+    /// parameter, such as `"`
+    InvalidKeyId,
+    /// An algorithm must be provided to sign a request
+    NoAlgorithm,
+    /// Headers that were selected for signing are missing from the request
+    MissingHeaders(String),
     /// An error occurred when signing the request
     SigningError,
     /// An error occurred when interacting with an RSA key
     BadPrivateKey,
+    /// The private key is for a different algorithm than the one requested, such as an EC key
+    /// used with `SignatureAlgorithm::RSA`
+    KeyAlgorithmMismatch,
+    /// The `expires` timestamp is earlier than the `created` timestamp
+    ExpiresBeforeCreated,
+    /// The generated header can't be stored as a header value
+    InvalidHeaderValue,
+    /// The request type can't be signed in the requested form, such as with a `host` derived from
+    /// its URI
+    UnsupportedRequest,
+    /// None of `(request-target)`, `date` and `(created)` are signed, and strict headers were
+    /// requested
+    WeakHeaders,
+    /// RFC 9530 only defines Content-Digest for SHA-256 and SHA-512
+    UnsupportedDigest,
+    /// The request already has an Authorization header with other credentials, such as a Bearer
+    /// token, which signing would overwrite
+    AuthorizationConflict,
 }
 
 impl From<IoError> for CreationError {
@@ -106,6 +187,7 @@ impl fmt::Display for CreationError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             CreationError::IO(ref io) => write!(f, "{}, {}", self.description(), io),
+            CreationError::MissingHeaders(ref mh) => write!(f, "{}, {}", self.description(), mh),
             _ => write!(f, "{}", self.description()),
         }
     }
@@ -116,8 +198,37 @@ impl StdError for CreationError {
         match *self {
             CreationError::IO(_) => "Signature creation: Error reading keys",
             CreationError::NoHeaders => "Signature creation: Must provide at least one header",
+            CreationError::NoKeyId => "Signature creation: Must provide a key id",
+            CreationError::InvalidKeyId => {
+                "Signature creation: Provided key id can't be used in a header"
+            }
+            CreationError::NoAlgorithm => "Signature creation: Must provide an algorithm",
+            CreationError::MissingHeaders(_) => {
+                "Signature creation: Headers selected for signing are not present"
+            }
             CreationError::SigningError => "Signature creation: Error signing",
             CreationError::BadPrivateKey => "Signature creation: Provided private key is invalid",
+            CreationError::KeyAlgorithmMismatch => {
+                "Signature creation: Provided private key is for a different algorithm"
+            }
+            CreationError::ExpiresBeforeCreated => {
+                "Signature creation: Provided expires timestamp is before created timestamp"
+            }
+            CreationError::InvalidHeaderValue => {
+                "Signature creation: Generated header is not a valid header value"
+            }
+            CreationError::UnsupportedRequest => {
+                "Signature creation: Request type can't be signed in the requested form"
+            }
+            CreationError::WeakHeaders => {
+                "Signature creation: Signed headers don't bind the signature to the request"
+            }
+            CreationError::UnsupportedDigest => {
+                "Signature creation: Content-Digest must use sha-256 or sha-512"
+            }
+            CreationError::AuthorizationConflict => {
+                "Signature creation: Request already has non-Signature Authorization credentials"
+            }
         }
     }
 
@@ -127,6 +238,13 @@ impl StdError for CreationError {
             _ => None,
         }
     }
+
+    fn source(&self) -> Option<&(StdError + 'static)> {
+        match *self {
+            CreationError::IO(ref ie) => Some(ie),
+            _ => None,
+        }
+    }
 }
 
 /// When decoding a signature doesn't work
@@ -138,6 +256,14 @@ pub enum DecodeError {
     InvalidAlgorithm(String),
     /// The key was not properly encoded to base64
     NotBase64,
+    /// A timestamp parameter is not a valid Unix timestamp
+    InvalidTimestamp(&'static str),
+    /// The Digest header is malformed, or uses no supported algorithm
+    InvalidDigest,
+    /// The header could not be split into key/value pairs
+    Malformed,
+    /// A parameter this crate doesn't know was found while parsing strictly
+    UnknownParameter(String),
 }
 
 impl fmt::Display for DecodeError {
@@ -145,6 +271,8 @@ impl fmt::Display for DecodeError {
         match *self {
             DecodeError::MissingKey(mk) => write!(f, "Missing key: {}", mk),
             DecodeError::InvalidAlgorithm(ref ia) => write!(f, "Invalid Algorithm: {}", ia),
+            DecodeError::InvalidTimestamp(it) => write!(f, "Invalid timestamp: {}", it),
+            DecodeError::UnknownParameter(ref up) => write!(f, "Unknown parameter: {}", up),
             _ => write!(f, "{}", self.description()),
         }
     }
@@ -156,6 +284,12 @@ impl StdError for DecodeError {
             DecodeError::MissingKey(_) => "Decoding: Missing key",
             DecodeError::InvalidAlgorithm(_) => "Decoding: Provided algorithm is not supported",
             DecodeError::NotBase64 => "Decoding: Provided signature is not base64 encoded",
+            DecodeError::InvalidTimestamp(_) => "Decoding: Provided timestamp is invalid",
+            DecodeError::InvalidDigest => "Decoding: Provided digest is invalid",
+            DecodeError::Malformed => "Decoding: Provided header is malformed",
+            DecodeError::UnknownParameter(_) => {
+                "Decoding: Provided header has an unknown parameter"
+            }
         }
     }
 
@@ -164,6 +298,10 @@ impl StdError for DecodeError {
             _ => None,
         }
     }
+
+    fn source(&self) -> Option<&(StdError + 'static)> {
+        None
+    }
 }
 
 /// When a request cannot be verified
@@ -173,16 +311,29 @@ pub enum VerificationError {
     Decode(DecodeError),
     /// Headers present in the `headers` field are missing from the request
     MissingHeaders(String),
+    /// A header required by `SignedHeader::require_headers` is not in the `headers` field
+    MissingSignedHeader(String),
     /// Problems reading headers
     Utf8(Utf8Error),
     /// When the `get_key` method from the `GetKey` type fails
     GetKey,
     /// Problems reading the required keys
     ReadKey,
-    /// Problems verifying the signature
+    /// The key returned by `GetKey` could not be parsed as a public key for the algorithm
+    BadPublicKey,
+    /// The signature claims a different algorithm than the key is for, such as an HMAC signature
+    /// checked against an RSA public key
+    AlgorithmMismatch,
+    /// The signature does not match the request
     BadSignature,
     /// When the Authorization header is missing
     HeaderNotPresent,
+    /// When the Digest header doesn't match the request body
+    DigestMismatch,
+    /// When the `expires` parameter is in the past, even allowing for clock skew
+    Expired,
+    /// When the `created` parameter is in the future, even allowing for clock skew
+    NotYetValid,
 }
 
 impl From<Utf8Error> for VerificationError {
@@ -204,6 +355,9 @@ impl fmt::Display for VerificationError {
             VerificationError::MissingHeaders(ref mh) => {
                 write!(f, "{}, {}", self.description(), mh)
             }
+            VerificationError::MissingSignedHeader(ref name) => {
+                write!(f, "{}, {}", self.description(), name)
+            }
             VerificationError::Utf8(ref ue) => write!(f, "Verification: reading headers: {}", ue),
             _ => write!(f, "{}", self.description()),
         }
@@ -217,11 +371,21 @@ impl StdError for VerificationError {
             VerificationError::MissingHeaders(_) => {
                 "Verification: Headers provided in headers field are not present in the request"
             }
+            VerificationError::MissingSignedHeader(_) => {
+                "Verification: A required header is not signed"
+            }
             VerificationError::Utf8(ref ue) => ue.description(),
             VerificationError::GetKey => "Verification: Error getting key",
             VerificationError::ReadKey => "Verification: Error reading key",
+            VerificationError::BadPublicKey => "Verification: Provided public key is invalid",
+            VerificationError::AlgorithmMismatch => {
+                "Verification: Signature algorithm doesn't match the key's algorithm"
+            }
             VerificationError::BadSignature => "Verification: Bad signature",
             VerificationError::HeaderNotPresent => "Verification: Header missing",
+            VerificationError::DigestMismatch => "Verification: Digest doesn't match body",
+            VerificationError::Expired => "Verification: Signature has expired",
+            VerificationError::NotYetValid => "Verification: Signature was created in the future",
         }
     }
 
@@ -232,4 +396,12 @@ impl StdError for VerificationError {
             _ => None,
         }
     }
+
+    fn source(&self) -> Option<&(StdError + 'static)> {
+        match *self {
+            VerificationError::Decode(ref de) => Some(de),
+            VerificationError::Utf8(ref ue) => Some(ue),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,614 @@
+// This file is part of HTTP Signatures
+
+// HTTP Signatures is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// HTTP Signatures is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module defines types for holding parsed keys.
+
+use std::fmt;
+use std::io::Read;
+use std::str;
+use std::sync::Arc;
+
+use base64::decode;
+use ring::{digest, hmac, rand, signature};
+use untrusted::Input;
+
+use error::{CreationError, VerificationError};
+use prelude::Signer;
+use super::{ShaSize, SignatureAlgorithm};
+use trace;
+
+/// The `SigningKey` struct holds a parsed private key, along with the key id and algorithm it is
+/// used with.
+///
+/// Parsing a key, especially an RSA key, costs much more than signing with it. Applications that
+/// sign many requests should create a `SigningKey` once and reuse it, rather than passing the raw
+/// key to `HttpSignature` for every request. Requests are signed with it through the
+/// `WithSigningKey` trait, and `benches/signing.rs` compares the two approaches.
+///
+/// ### Example
+/// ```rust
+/// # use std::fs::File;
+/// # use http_signatures::Error;
+/// use http_signatures::{SigningKey, SignatureAlgorithm, ShaSize};
+///
+/// # fn run() -> Result<(), Error> {
+/// let priv_key = File::open("tests/assets/private.der")?;
+/// let alg = SignatureAlgorithm::RSA(ShaSize::FiveTwelve);
+///
+/// let signing_key = SigningKey::new("rsa-key-1".into(), priv_key, alg)?;
+///
+/// let signature = signing_key.sign("(request-target): get /")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ### Signing in async code
+///
+/// `WithHttpSignature` reads its key on every call, which blocks the executor when the key is a
+/// `File`. In async code, create the `SigningKey` once, before serving requests or with the
+/// executor's facility for blocking work, and sign with the `WithSigningKey` methods, which never
+/// read a key.
+///
+/// ```rust,ignore
+/// let signing_key = SigningKey::new(key_id, File::open("private.der")?, alg)?;
+///
+/// // later, for every request
+/// req.with_authorization_header_using(&signing_key)?;
+/// ```
+pub struct SigningKey {
+    key_id: String,
+    algorithm: SignatureAlgorithm,
+    key: ParsedKey,
+}
+
+enum ParsedKey {
+    RSA(Arc<signature::RSAKeyPair>, ShaSize, RSAPadding),
+    HMAC(hmac::SigningKey),
+    Ed25519(signature::Ed25519KeyPair),
+}
+
+enum RSAPadding {
+    PKCS1,
+    PSS,
+}
+
+/// The kinds of private key that can be told apart by their encoding
+#[derive(PartialEq)]
+enum KeyKind {
+    RSA,
+    EC,
+    Ed25519,
+    Other,
+}
+
+impl SigningKey {
+    /// Read and parse a private key.
+    ///
+    /// RSA keys are read as PKCS#1 or PKCS#8 documents, and Ed25519 keys as PKCS#8 documents.
+    /// These may be DER or unencrypted PEM encoded; PEM keys are recognized by their `-----BEGIN`
+    /// line. HMAC keys are read as the raw shared secret. This method will Error if the key can't
+    /// be read or parsed, or if the key id is invalid.
+    pub fn new<T>(
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<Self, CreationError>
+    where
+        T: Read,
+    {
+        check_key_id(&key_id)?;
+
+        let mut signing_key = Self::without_key_id(key, algorithm)?;
+        signing_key.key_id = key_id;
+
+        Ok(signing_key)
+    }
+
+    /// Read and parse a private key for signing data that isn't sent in a header, and so needs no
+    /// key id.
+    pub(crate) fn without_key_id<T>(
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<Self, CreationError>
+    where
+        T: Read,
+    {
+        let span = trace::parse_key_span(&algorithm);
+        let _guard = span.enter();
+        let signing_key = Self::parse(key, algorithm);
+        trace::creation(&signing_key, "parsing the key");
+
+        signing_key
+    }
+
+    fn parse<T>(mut key: T, algorithm: SignatureAlgorithm) -> Result<Self, CreationError>
+    where
+        T: Read,
+    {
+        let mut key_bytes = Vec::new();
+        key.read_to_end(&mut key_bytes)?;
+
+        if let Some(kind) = private_key_kind(&key_bytes) {
+            let expected = match algorithm {
+                SignatureAlgorithm::RSA(_) | SignatureAlgorithm::RSAPSS(_) => Some(KeyKind::RSA),
+                SignatureAlgorithm::Ed25519 => Some(KeyKind::Ed25519),
+                // A private key is never meant as a shared secret
+                SignatureAlgorithm::HMAC(_) => None,
+            };
+
+            if expected != Some(kind) {
+                return Err(CreationError::KeyAlgorithmMismatch);
+            }
+        }
+
+        let key = match algorithm {
+            SignatureAlgorithm::RSA(ref size) | SignatureAlgorithm::RSAPSS(ref size) => {
+                let key_bytes = match pem_to_der(&key_bytes)? {
+                    Some((label, der)) => match label.as_str() {
+                        PKCS1_LABEL => der,
+                        PKCS8_LABEL => pkcs8_private_key(&der)?.to_vec(),
+                        _ => return Err(CreationError::BadPrivateKey),
+                    },
+                    // A DER PKCS#8 document wraps the PKCS#1 document Ring reads
+                    None => {
+                        let pkcs1 = pkcs8_private_key(&key_bytes).ok().map(|der| der.to_vec());
+                        pkcs1.unwrap_or(key_bytes)
+                    }
+                };
+
+                let key_pair = signature::RSAKeyPair::from_der(Input::from(&key_bytes))
+                    .map_err(|_| CreationError::BadPrivateKey)?;
+
+                let padding = match algorithm {
+                    SignatureAlgorithm::RSAPSS(_) => RSAPadding::PSS,
+                    _ => RSAPadding::PKCS1,
+                };
+
+                ParsedKey::RSA(Arc::new(key_pair), size.clone(), padding)
+            }
+            SignatureAlgorithm::HMAC(ref size) => ParsedKey::HMAC(hmac::SigningKey::new(
+                match *size {
+                    ShaSize::TwoFiftySix => &digest::SHA256,
+                    ShaSize::ThreeEightyFour => &digest::SHA384,
+                    ShaSize::FiveTwelve => &digest::SHA512,
+                },
+                &key_bytes,
+            )),
+            SignatureAlgorithm::Ed25519 => {
+                let key_bytes = match pem_to_der(&key_bytes)? {
+                    Some((label, der)) => match label.as_str() {
+                        PKCS8_LABEL => der,
+                        _ => return Err(CreationError::BadPrivateKey),
+                    },
+                    None => key_bytes,
+                };
+
+                let key_pair = signature::Ed25519KeyPair::from_pkcs8(Input::from(&key_bytes))
+                    .map_err(|_| CreationError::BadPrivateKey)?;
+
+                ParsedKey::Ed25519(key_pair)
+            }
+        };
+
+        Ok(SigningKey {
+            key_id: String::new(),
+            algorithm,
+            key,
+        })
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub fn algorithm(&self) -> &SignatureAlgorithm {
+        &self.algorithm
+    }
+
+    /// Sign the given signing string, returning the raw signature bytes.
+    pub fn sign(&self, signing_string: &str) -> Result<Vec<u8>, CreationError> {
+        self.sign_bytes(signing_string.as_bytes())
+    }
+
+    fn sign_bytes(&self, data: &[u8]) -> Result<Vec<u8>, CreationError> {
+        match self.key {
+            ParsedKey::RSA(ref key_pair, ref size, ref padding) => {
+                // PSS salts are as long as the digest
+                let encoding: &'static signature::RSAEncoding = match (padding, size) {
+                    (&RSAPadding::PKCS1, &ShaSize::TwoFiftySix) => &signature::RSA_PKCS1_SHA256,
+                    (&RSAPadding::PKCS1, &ShaSize::ThreeEightyFour) => &signature::RSA_PKCS1_SHA384,
+                    (&RSAPadding::PKCS1, &ShaSize::FiveTwelve) => &signature::RSA_PKCS1_SHA512,
+                    (&RSAPadding::PSS, &ShaSize::TwoFiftySix) => &signature::RSA_PSS_SHA256,
+                    (&RSAPadding::PSS, &ShaSize::ThreeEightyFour) => &signature::RSA_PSS_SHA384,
+                    (&RSAPadding::PSS, &ShaSize::FiveTwelve) => &signature::RSA_PSS_SHA512,
+                };
+
+                let mut signing_state = signature::RSASigningState::new(key_pair.clone())
+                    .map_err(|_| CreationError::SigningError)?;
+
+                let rng = rand::SystemRandom::new();
+                let mut signature = vec![0; signing_state.key_pair().public_modulus_len()];
+                signing_state
+                    .sign(encoding, &rng, data, signature.as_mut_slice())
+                    .map_err(|_| CreationError::SigningError)?;
+
+                Ok(signature)
+            }
+            ParsedKey::HMAC(ref hmac_key) => {
+                let signature = hmac::sign(hmac_key, data);
+
+                Ok(signature.as_ref().to_vec())
+            }
+            ParsedKey::Ed25519(ref key_pair) => {
+                let signature = key_pair.sign(data);
+
+                Ok(signature.as_ref().to_vec())
+            }
+        }
+    }
+}
+
+impl Signer for SigningKey {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    fn algorithm(&self) -> &SignatureAlgorithm {
+        &self.algorithm
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, CreationError> {
+        self.sign_bytes(data)
+    }
+}
+
+/// The `PublicKey` struct holds a public key, along with the algorithm it verifies signatures for.
+///
+/// RSA keys are stored as a PKCS#1 RSAPublicKey document and Ed25519 keys as their raw 32 bytes,
+/// which is what the verifier reads. A `HashMap` of key ids to `PublicKey`s can be used as a
+/// `GetKey` type, and only hands keys to signatures that claim the key's algorithm, so a public RSA
+/// key can never be used as an HMAC secret.
+///
+/// ### Example
+/// ```rust
+/// # use std::fs::File;
+/// # use std::io::Read;
+/// # use http_signatures::Error;
+/// use http_signatures::{PublicKey, SignatureAlgorithm, ShaSize};
+///
+/// # fn run() -> Result<(), Error> {
+/// let mut pem = Vec::new();
+/// File::open("tests/assets/public.pem")?.read_to_end(&mut pem)?;
+///
+/// let public_key = PublicKey::from_pem(&pem, SignatureAlgorithm::RSA(ShaSize::FiveTwelve))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PublicKey {
+    algorithm: SignatureAlgorithm,
+    key: Vec<u8>,
+}
+
+impl PublicKey {
+    /// Parse a DER encoded public key.
+    ///
+    /// RSA keys may be PKCS#1 RSAPublicKey or SubjectPublicKeyInfo documents, and Ed25519 keys
+    /// SubjectPublicKeyInfo documents. HMAC uses shared secrets rather than public keys, so it is
+    /// rejected along with malformed keys, with `VerificationError::BadPublicKey`.
+    pub fn from_der(der: &[u8], algorithm: SignatureAlgorithm) -> Result<Self, VerificationError> {
+        let key = match algorithm {
+            SignatureAlgorithm::RSA(_) | SignatureAlgorithm::RSAPSS(_) => {
+                if is_rsa_public_key(der) {
+                    der.to_vec()
+                } else {
+                    match spki_public_key(der) {
+                        Some((oid, key)) if oid == RSA_OID && is_rsa_public_key(key) => {
+                            key.to_vec()
+                        }
+                        _ => return Err(VerificationError::BadPublicKey),
+                    }
+                }
+            }
+            SignatureAlgorithm::Ed25519 => match spki_public_key(der) {
+                Some((oid, key)) if oid == ED25519_OID => return Self::from_ed25519_bytes(key),
+                _ => return Err(VerificationError::BadPublicKey),
+            },
+            SignatureAlgorithm::HMAC(_) => return Err(VerificationError::BadPublicKey),
+        };
+
+        Ok(PublicKey { algorithm, key })
+    }
+
+    /// Parse a PEM encoded public key, labelled `PUBLIC KEY` for SubjectPublicKeyInfo documents
+    /// or `RSA PUBLIC KEY` for PKCS#1 documents.
+    pub fn from_pem(pem: &[u8], algorithm: SignatureAlgorithm) -> Result<Self, VerificationError> {
+        let (label, der) = match pem_to_der(pem) {
+            Ok(Some(pem)) => pem,
+            _ => return Err(VerificationError::BadPublicKey),
+        };
+
+        match label.as_str() {
+            SPKI_LABEL | PKCS1_PUBLIC_LABEL => Self::from_der(&der, algorithm),
+            _ => Err(VerificationError::BadPublicKey),
+        }
+    }
+
+    /// Use the raw 32 bytes of an Ed25519 public key.
+    pub fn from_ed25519_bytes(bytes: &[u8]) -> Result<Self, VerificationError> {
+        if bytes.len() != ED25519_PUBLIC_KEY_LEN {
+            return Err(VerificationError::BadPublicKey);
+        }
+
+        Ok(PublicKey {
+            algorithm: SignatureAlgorithm::Ed25519,
+            key: bytes.to_vec(),
+        })
+    }
+
+    pub fn algorithm(&self) -> &SignatureAlgorithm {
+        &self.algorithm
+    }
+
+    /// The key, in the form the verifier reads
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.key
+    }
+}
+
+/// The `Debug` implementation for `SigningKey` never prints key material.
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("key_id", &self.key_id)
+            .field("algorithm", &self.algorithm)
+            .finish()
+    }
+}
+
+/// Check that a key id can be placed in the quoted `keyId` parameter without escaping. Quotes and
+/// backslashes are rejected, since they could end the parameter early or add new ones, along with
+/// control characters and non-ASCII characters, which aren't allowed in header values.
+pub(crate) fn check_key_id(key_id: &str) -> Result<(), CreationError> {
+    let valid = !key_id.is_empty()
+        && key_id
+            .chars()
+            .all(|c| c >= ' ' && c <= '~' && c != '"' && c != '\\');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(CreationError::InvalidKeyId)
+    }
+}
+
+const PEM_BEGIN: &str = "-----BEGIN ";
+const PEM_END: &str = "-----END ";
+const BYTE_ORDER_MARK: char = '\u{feff}';
+const PKCS1_LABEL: &str = "RSA PRIVATE KEY";
+const PKCS8_LABEL: &str = "PRIVATE KEY";
+const SEC1_LABEL: &str = "EC PRIVATE KEY";
+const SPKI_LABEL: &str = "PUBLIC KEY";
+const PKCS1_PUBLIC_LABEL: &str = "RSA PUBLIC KEY";
+
+pub(crate) const ED25519_PUBLIC_KEY_LEN: usize = 32;
+
+const DER_SEQUENCE: u8 = 0x30;
+const DER_INTEGER: u8 = 0x02;
+const DER_BIT_STRING: u8 = 0x03;
+const DER_OCTET_STRING: u8 = 0x04;
+const DER_OID: u8 = 0x06;
+
+// The DER contents of the rsaEncryption and id-Ed25519 object identifiers
+const RSA_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+const ED25519_OID: &[u8] = &[0x2b, 0x65, 0x70];
+const EC_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+
+/// Decode a PEM document into its label and DER contents, or return `None` if the key isn't PEM
+/// encoded. Encrypted PEM documents are rejected.
+///
+/// Lines may end with `\n`, `\r\n` or a lone `\r`, and a leading byte order mark is skipped, since
+/// editors on Windows often save key files that way.
+fn pem_to_der(key: &[u8]) -> Result<Option<(String, Vec<u8>)>, CreationError> {
+    let pem = match str::from_utf8(key) {
+        Ok(pem) => pem.trim_left_matches(BYTE_ORDER_MARK),
+        _ => return Ok(None),
+    };
+
+    if !pem.trim_left().starts_with(PEM_BEGIN) {
+        return Ok(None);
+    }
+
+    let mut lines = pem.split(|c: char| c == '\r' || c == '\n')
+        .map(|line| line.trim())
+        .skip_while(|line| line.is_empty());
+
+    let label = lines
+        .next()
+        .and_then(|line| {
+            if line.starts_with(PEM_BEGIN) && line.ends_with("-----") {
+                Some(line[PEM_BEGIN.len()..line.len() - 5].to_owned())
+            } else {
+                None
+            }
+        })
+        .ok_or(CreationError::BadPrivateKey)?;
+
+    let mut body = String::new();
+    let mut ended = false;
+
+    for line in lines {
+        if line.starts_with(PEM_END) {
+            ended = true;
+            break;
+        }
+
+        // Headers such as `Proc-Type: 4,ENCRYPTED` mean the key is encrypted, which we can't read
+        if line.contains(':') {
+            return Err(CreationError::BadPrivateKey);
+        }
+
+        body.push_str(line);
+    }
+
+    if !ended {
+        return Err(CreationError::BadPrivateKey);
+    }
+
+    let der = decode(&body).map_err(|_| CreationError::BadPrivateKey)?;
+
+    Ok(Some((label, der)))
+}
+
+/// Tell what kind of private key `key` is from its PEM label or DER structure, or return `None`
+/// if it isn't a private key document this can recognize.
+fn private_key_kind(key: &[u8]) -> Option<KeyKind> {
+    match pem_to_der(key) {
+        Ok(Some((label, der))) => match label.as_str() {
+            PKCS1_LABEL => Some(KeyKind::RSA),
+            SEC1_LABEL => Some(KeyKind::EC),
+            PKCS8_LABEL => Some(pkcs8_kind(&der).unwrap_or(KeyKind::Other)),
+            _ => None,
+        },
+        Ok(None) => pkcs8_kind(key).or_else(|| der_private_key_kind(key)),
+        Err(_) => None,
+    }
+}
+
+/// Read the algorithm of a PKCS#8 PrivateKeyInfo document
+fn pkcs8_kind(der: &[u8]) -> Option<KeyKind> {
+    let oid = der_element(der, DER_SEQUENCE)
+        .and_then(|(private_key_info, _)| der_element(private_key_info, DER_INTEGER))
+        .and_then(|(_version, rest)| der_element(rest, DER_SEQUENCE))
+        .and_then(|(algorithm, _)| der_element(algorithm, DER_OID))
+        .map(|(oid, _)| oid)?;
+
+    Some(if oid == RSA_OID {
+        KeyKind::RSA
+    } else if oid == ED25519_OID {
+        KeyKind::Ed25519
+    } else if oid == EC_OID {
+        KeyKind::EC
+    } else {
+        KeyKind::Other
+    })
+}
+
+/// Recognize PKCS#1 RSAPrivateKey documents, which start with version 0 and the modulus, and SEC1
+/// ECPrivateKey documents, which start with version 1 and the private key octet string.
+fn der_private_key_kind(der: &[u8]) -> Option<KeyKind> {
+    let (private_key, _) = der_element(der, DER_SEQUENCE)?;
+    let (version, rest) = der_element(private_key, DER_INTEGER)?;
+
+    if version == [0] && der_element(rest, DER_INTEGER).is_some() {
+        Some(KeyKind::RSA)
+    } else if version == [1] && der_element(rest, DER_OCTET_STRING).is_some() {
+        Some(KeyKind::EC)
+    } else {
+        None
+    }
+}
+
+/// Extract the privateKey field of a PKCS#8 PrivateKeyInfo document. For RSA keys this is the
+/// PKCS#1 document Ring expects.
+fn pkcs8_private_key(der: &[u8]) -> Result<&[u8], CreationError> {
+    let private_key = der_element(der, DER_SEQUENCE)
+        .and_then(|(private_key_info, _)| der_element(private_key_info, DER_INTEGER))
+        .and_then(|(_version, rest)| der_element(rest, DER_SEQUENCE))
+        .and_then(|(_algorithm, rest)| der_element(rest, DER_OCTET_STRING))
+        .map(|(private_key, _)| private_key)
+        .ok_or(CreationError::BadPrivateKey)?;
+
+    Ok(private_key)
+}
+
+/// Check whether `key` is a PKCS#1 or SubjectPublicKeyInfo public key, DER or PEM encoded
+pub(crate) fn is_public_key(key: &[u8]) -> bool {
+    match pem_to_der(key) {
+        Ok(Some((label, _))) => label == SPKI_LABEL || label == PKCS1_PUBLIC_LABEL,
+        _ => is_rsa_public_key(key) || spki_public_key(key).is_some(),
+    }
+}
+
+/// Split a SubjectPublicKeyInfo document into the object identifier of its algorithm and the
+/// contents of its subjectPublicKey bit string.
+fn spki_public_key(der: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (spki, rest) = der_element(der, DER_SEQUENCE)?;
+
+    if !rest.is_empty() {
+        return None;
+    }
+
+    let (algorithm, rest) = der_element(spki, DER_SEQUENCE)?;
+    let (oid, _parameters) = der_element(algorithm, DER_OID)?;
+    let (public_key, rest) = der_element(rest, DER_BIT_STRING)?;
+
+    // The first byte of a bit string counts its unused bits, which keys never have
+    match public_key.split_first() {
+        Some((&0, key)) if rest.is_empty() => Some((oid, key)),
+        _ => None,
+    }
+}
+
+/// Check that `der` is shaped like a PKCS#1 RSAPublicKey, a sequence of the modulus and public
+/// exponent. Ring doesn't distinguish a malformed key from a bad signature, so this lets
+/// verification report the two separately.
+pub(crate) fn is_rsa_public_key(der: &[u8]) -> bool {
+    der_element(der, DER_SEQUENCE)
+        .and_then(|(public_key, rest)| {
+            if rest.is_empty() {
+                Some(public_key)
+            } else {
+                None
+            }
+        })
+        .and_then(|public_key| der_element(public_key, DER_INTEGER))
+        .and_then(|(_modulus, rest)| der_element(rest, DER_INTEGER))
+        .map(|(_exponent, rest)| rest.is_empty())
+        .unwrap_or(false)
+}
+
+/// Split a DER element with the given tag off the front of `input`, returning its contents and
+/// the remaining input.
+fn der_element(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    if input.len() < 2 || input[0] != tag {
+        return None;
+    }
+
+    let (len, header_len) = if input[1] < 0x80 {
+        (input[1] as usize, 2)
+    } else {
+        let len_bytes = (input[1] & 0x7f) as usize;
+
+        if len_bytes == 0 || len_bytes > 4 || input.len() < 2 + len_bytes {
+            return None;
+        }
+
+        let len = input[2..2 + len_bytes]
+            .iter()
+            .fold(0, |len, byte| (len << 8) | *byte as usize);
+
+        (len, 2 + len_bytes)
+    };
+
+    // A four byte length can overflow usize on 32 bit targets
+    let end = header_len.checked_add(len)?;
+
+    if input.len() < end {
+        return None;
+    }
+
+    Some((&input[header_len..end], &input[end..]))
+}
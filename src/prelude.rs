@@ -15,11 +15,15 @@
 
 //! This module defines useful traits for using HTTP Signatures.
 
+use std::collections::{BTreeMap, HashMap};
 use std::io::Read;
+use std::time::SystemTime;
 
-use create::HttpSignature;
-use error::{Error, VerificationError};
-use super::SignatureAlgorithm;
+use create::{HttpSignature, SignatureParams};
+use error::{CreationError, Error, VerificationError};
+use key::PublicKey;
+use verify::Verification;
+use super::{ShaSize, SignatureAlgorithm};
 
 /// `AsHttpSignature` defines a trait for getting an Authorization or Signature Header string from
 /// any type that implements it. It provides three methods: `as_http_signature`, which implementors
@@ -37,6 +41,93 @@ where
         algorithm: SignatureAlgorithm,
     ) -> Result<HttpSignature<T>, Error>;
 
+    /// Gets an `HttpSignature` struct from an immutably borrowed Self, which only signs the named
+    /// headers in the order they are given
+    ///
+    /// This Errors if one of the named headers is not present.
+    fn as_http_signature_with_headers(
+        &self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+        header_names: &[&str],
+    ) -> Result<HttpSignature<T>, Error> {
+        Ok(self.as_http_signature(key_id, key, algorithm)?
+            .with_signed_headers(header_names)?)
+    }
+
+    /// Gets an `HttpSignature` struct from an immutably borrowed Self, deriving a `host` header
+    /// from the authority of the request's URI if the request has no Host header
+    ///
+    /// Signing `host` is often required by verifiers, but a request may not get its Host header
+    /// until it is sent. Requests whose URI has no authority sign the same headers as
+    /// `as_http_signature`.
+    ///
+    /// The provided implementation fails with `CreationError::UnsupportedRequest`, so that a type
+    /// that can't derive a host never signs without one.
+    fn as_http_signature_with_derived_host(
+        &self,
+        _key_id: String,
+        _key: T,
+        _algorithm: SignatureAlgorithm,
+    ) -> Result<HttpSignature<T>, Error> {
+        Err(CreationError::UnsupportedRequest.into())
+    }
+
+    /// Gets an `HttpSignature` struct from an immutably borrowed Self, with the
+    /// `(request-target)` pseudo-header in absolute form, such as `get http://example.org/foo`
+    ///
+    /// This is only meant for requests sent through forward proxies. Requests without an absolute
+    /// URI sign the same headers as `as_http_signature`.
+    ///
+    /// The provided implementation fails with `CreationError::UnsupportedRequest`, so that a type
+    /// that can't build the absolute form never signs the origin form in its place.
+    fn as_http_signature_absolute_form(
+        &self,
+        _key_id: String,
+        _key: T,
+        _algorithm: SignatureAlgorithm,
+    ) -> Result<HttpSignature<T>, Error> {
+        Err(CreationError::UnsupportedRequest.into())
+    }
+
+    /// Gets an `HttpSignature` struct from an immutably borrowed Self, with the parameters of the
+    /// query in `(request-target)` sorted by `sorted_query`
+    ///
+    /// The specification signs the query as it was sent, so this only verifies against servers
+    /// that sort the query the same way. Requests without a query sign the same headers as
+    /// `as_http_signature`.
+    ///
+    /// The provided implementation fails with `CreationError::UnsupportedRequest`, so that a type
+    /// that can't sort its query never signs it unsorted.
+    fn as_http_signature_with_sorted_query(
+        &self,
+        _key_id: String,
+        _key: T,
+        _algorithm: SignatureAlgorithm,
+    ) -> Result<HttpSignature<T>, Error> {
+        Err(CreationError::UnsupportedRequest.into())
+    }
+
+    /// Gets an `HttpSignature` struct from an immutably borrowed Self, signing the request's
+    /// headers in the order they were added to the request rather than in alphabetical order
+    ///
+    /// The order of the headers in the `headers` parameter doesn't matter to verifiers that follow
+    /// the specification, since they rebuild the signing string in that order. Some verifiers
+    /// instead expect the headers in the order they were sent, and reject the alphabetical order
+    /// used by `as_http_signature`. `(request-target)` is always signed first.
+    ///
+    /// The provided implementation fails with `CreationError::UnsupportedRequest`, so that a type
+    /// that doesn't keep the order of its headers never signs them in alphabetical order instead.
+    fn as_http_signature_in_request_order(
+        &self,
+        _key_id: String,
+        _key: T,
+        _algorithm: SignatureAlgorithm,
+    ) -> Result<HttpSignature<T>, Error> {
+        Err(CreationError::UnsupportedRequest.into())
+    }
+
     /// Generates the Authorization Header from an immutably borrowed Self
     fn authorization_header(
         &self,
@@ -62,6 +153,11 @@ where
 
 /// `WithHttpSignature` defines a trait for adding Authorization and Signature headers to another
 /// library's request or response object.
+///
+/// Adding an Authorization header replaces one that uses the `Signature` scheme, but fails with
+/// `CreationError::AuthorizationConflict` if the request already carries other credentials, such
+/// as a Bearer token, rather than silently dropping them. Use a Signature header for those
+/// requests instead.
 pub trait WithHttpSignature<T>: AsHttpSignature<T>
 where
     T: Read,
@@ -79,12 +175,383 @@ where
         key: T,
         algorithm: SignatureAlgorithm,
     ) -> Result<&mut Self, Error>;
+
+    /// Adds an Authorization header, and returns the request by value
+    ///
+    /// This suits requests that are built inline and then moved, such as
+    /// `Request::get(url).body(())?.into_signed_with_authorization(key_id, key, algorithm)?`.
+    fn into_signed_with_authorization(
+        mut self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        self.with_authorization_header(key_id, key, algorithm).map(|_| ())?;
+        Ok(self)
+    }
+
+    /// Adds a Signature header, and returns the request by value
+    fn into_signed_with_signature(
+        mut self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        self.with_signature_header(key_id, key, algorithm).map(|_| ())?;
+        Ok(self)
+    }
+
+    /// Adds an Authorization header which only signs the named headers, in the order they are
+    /// given
+    ///
+    /// This Errors if one of the named headers is not present.
+    fn with_authorization_header_selective(
+        &mut self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+        header_names: &[&str],
+    ) -> Result<&mut Self, Error>
+    where
+        Self: SetSignatureHeaders,
+    {
+        let auth_header = self.as_http_signature_with_headers(key_id, key, algorithm, header_names)?
+            .authorization_header()?;
+
+        self.set_authorization_header(auth_header)
+    }
+
+    /// Adds a Signature header which only signs the named headers, in the order they are given
+    ///
+    /// This Errors if one of the named headers is not present.
+    fn with_signature_header_selective(
+        &mut self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+        header_names: &[&str],
+    ) -> Result<&mut Self, Error>
+    where
+        Self: SetSignatureHeaders,
+    {
+        let sig_header = self.as_http_signature_with_headers(key_id, key, algorithm, header_names)?
+            .signature_header()?;
+
+        self.set_signature_header(sig_header)
+    }
+
+    /// Adds a Digest header for `body`, and then an Authorization header signing the request,
+    /// including the new Digest header
+    ///
+    /// `digest_size` is independent of the hash used by `algorithm`, so a request may be signed
+    /// with RSA-SHA512 over a SHA-256 Digest header. Verifiers read the digest's algorithm from
+    /// the Digest header itself.
+    fn with_digest_and_authorization_header(
+        &mut self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+        body: &[u8],
+        digest_size: ShaSize,
+    ) -> Result<&mut Self, Error>
+    where
+        Self: WithDigest,
+    {
+        self.with_digest_header(body, digest_size);
+        self.with_authorization_header(key_id, key, algorithm)
+    }
+
+    /// Adds a Digest header for the request's own body, and then an Authorization header signing
+    /// the request, including the new Digest header
+    ///
+    /// The body is only read, so it is still sent as it is. Request types whose body can't be read
+    /// without consuming it, such as `hyper::Request`, should use
+    /// `with_digest_and_authorization_header` with the bytes of the body instead.
+    fn with_authorization_header_and_digest(
+        &mut self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+        digest_size: ShaSize,
+    ) -> Result<&mut Self, Error>
+    where
+        Self: WithDigest + ReadBody,
+    {
+        let body = self.body_bytes().to_vec();
+
+        self.with_digest_and_authorization_header(key_id, key, algorithm, &body, digest_size)
+    }
+
+    /// Adds a Date header with the current time if the request has none, and then an
+    /// Authorization header signing the request, including the Date header
+    fn with_date_and_authorization_header(
+        &mut self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<&mut Self, Error>
+    where
+        Self: WithDate,
+    {
+        self.with_date_and_authorization_header_from(key_id, key, algorithm, &SystemClock)
+    }
+
+    /// Like `with_date_and_authorization_header`, but the Date header is read from `clock`
+    /// rather than the system clock, so tests can sign at a fixed time
+    fn with_date_and_authorization_header_from<C: Clock>(
+        &mut self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+        clock: &C,
+    ) -> Result<&mut Self, Error>
+    where
+        Self: WithDate,
+    {
+        self.with_date_header_if_missing(clock.now());
+        self.with_authorization_header(key_id, key, algorithm)
+    }
+
+    /// Adds a Date header with the current time if the request has none, and then a Signature
+    /// header signing the request, including the Date header
+    fn with_date_and_signature_header(
+        &mut self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<&mut Self, Error>
+    where
+        Self: WithDate,
+    {
+        self.with_date_and_signature_header_from(key_id, key, algorithm, &SystemClock)
+    }
+
+    /// Like `with_date_and_signature_header`, but the Date header is read from `clock` rather
+    /// than the system clock, so tests can sign at a fixed time
+    fn with_date_and_signature_header_from<C: Clock>(
+        &mut self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+        clock: &C,
+    ) -> Result<&mut Self, Error>
+    where
+        Self: WithDate,
+    {
+        self.with_date_header_if_missing(clock.now());
+        self.with_signature_header(key_id, key, algorithm)
+    }
+}
+
+/// `ReadBody` is implemented by request types whose whole body can be read without consuming it,
+/// so that a Digest header can be computed from it.
+pub trait ReadBody {
+    fn body_bytes(&self) -> &[u8];
+}
+
+/// `SetSignatureHeaders` is implemented by request types that can be given the value of an
+/// Authorization or Signature header directly, so that `WithHttpSignature` can provide ways of
+/// signing them, such as `with_authorization_header_selective`, which build the header themselves.
+pub trait SetSignatureHeaders {
+    /// Sets the Authorization header to `value`
+    ///
+    /// This fails with `CreationError::AuthorizationConflict` if the request already carries an
+    /// Authorization header that doesn't use the `Signature` scheme.
+    fn set_authorization_header(&mut self, value: String) -> Result<&mut Self, Error>;
+
+    /// Sets the Signature header to `value`
+    fn set_signature_header(&mut self, value: String) -> Result<&mut Self, Error>;
+}
+
+/// The `Signer` trait performs the signing operation for the `_using` methods, such as
+/// `with_authorization_header_using`.
+///
+/// `SigningKey` is the built-in `Signer`, and holds its key in memory. Implementing `Signer` for
+/// a type that calls out to an HSM, a PKCS#11 library, or a cloud KMS lets requests be signed
+/// without the private key ever being loaded into the process.
+///
+/// ### Example
+/// ```rust
+/// # use http_signatures::{CreationError, SignatureAlgorithm, ShaSize};
+/// use http_signatures::prelude::*;
+///
+/// struct KmsSigner {
+///     key_id: String,
+///     algorithm: SignatureAlgorithm,
+/// }
+///
+/// impl Signer for KmsSigner {
+///     fn key_id(&self) -> &str {
+///         &self.key_id
+///     }
+///
+///     fn algorithm(&self) -> &SignatureAlgorithm {
+///         &self.algorithm
+///     }
+///
+///     fn sign(&self, data: &[u8]) -> Result<Vec<u8>, CreationError> {
+///         // Send `data` to the KMS, and return the signature it produces
+/// #       let _ = data;
+///         Err(CreationError::SigningError)
+///     }
+/// }
+/// ```
+pub trait Signer {
+    /// The keyId field of the headers this signs
+    fn key_id(&self) -> &str;
+
+    /// The algorithm the signature is created with
+    fn algorithm(&self) -> &SignatureAlgorithm;
+
+    /// Sign `data`, returning the raw signature bytes
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, CreationError>;
+}
+
+/// `AsSignatureParams` defines a trait for getting the `SignatureParams` of a request, which hold
+/// everything about its signature but the key.
+///
+/// This is what the `_using` methods build on: the parameters are signed with a `Signer`, so they
+/// never need a key to read. `as_http_signature` adds the key to these same parameters.
+pub trait AsSignatureParams {
+    /// Gets the `SignatureParams` of an immutably borrowed Self, signing every header
+    fn as_signature_params(
+        &self,
+        key_id: String,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<SignatureParams, Error>;
+
+    /// Gets the `SignatureParams` of an immutably borrowed Self, which only sign the named
+    /// headers in the order they are given
+    ///
+    /// This Errors if one of the named headers is not present.
+    fn as_signature_params_with_headers(
+        &self,
+        key_id: String,
+        algorithm: SignatureAlgorithm,
+        header_names: &[&str],
+    ) -> Result<SignatureParams, Error> {
+        Ok(self.as_signature_params(key_id, algorithm)?
+            .with_signed_headers(header_names)?)
+    }
+}
+
+/// `WithSigningKey` defines a trait for adding Authorization and Signature headers signed with a
+/// `Signer`, such as a pre-parsed `SigningKey`.
+///
+/// `WithHttpSignature` reads and parses its key on every call, which is blocking I/O when the key
+/// is a `File`. These methods never read a key, so they are the ones to use from async code. See
+/// the `SigningKey` documentation for the recommended pattern.
+pub trait WithSigningKey {
+    fn with_authorization_header_using<S: Signer>(&mut self, key: &S) -> Result<&mut Self, Error>;
+
+    fn with_signature_header_using<S: Signer>(&mut self, key: &S) -> Result<&mut Self, Error>;
+}
+
+/// `Resign` defines a trait for replacing the signature of a request that was changed after it
+/// was signed, such as a retry that adds a header.
+///
+/// A stale signature left on a request fails verification, or worse, is sent alongside a new
+/// one. `resign_using` removes the old signature before signing the current headers, so it can be
+/// called any number of times.
+pub trait Resign: WithSigningKey {
+    /// Whether the request is signed with a Signature header, rather than an Authorization header
+    fn has_signature_header(&self) -> bool;
+
+    /// Removes the Signature header, and the Authorization header if it uses the `Signature`
+    /// scheme. Other Authorization headers are left alone.
+    fn remove_signature_headers(&mut self) -> &mut Self;
+
+    /// Removes any existing signature, and signs the request again with `key`
+    ///
+    /// A request that was signed with a Signature header is signed with a Signature header
+    /// again, otherwise an Authorization header is added.
+    fn resign_using<S: Signer>(&mut self, key: &S) -> Result<&mut Self, Error> {
+        let signature_header = self.has_signature_header();
+        self.remove_signature_headers();
+
+        if signature_header {
+            self.with_signature_header_using(key)
+        } else {
+            self.with_authorization_header_using(key)
+        }
+    }
+}
+
+/// Gets the `SignatureParams` for `req`, with the key id and algorithm of `key`, which signs them
+pub(crate) fn signature_params_for<R, S>(req: &R, key: &S) -> Result<SignatureParams, Error>
+where
+    R: AsSignatureParams,
+    S: Signer,
+{
+    req.as_signature_params(key.key_id().to_owned(), key.algorithm().clone())
+}
+
+/// `WithDigest` defines a trait for adding a Digest header to another library's request object.
+pub trait WithDigest {
+    /// Adds a Digest header containing the hash of `body`
+    ///
+    /// The request does not expose its body, so this must be given the same bytes that are sent
+    /// as the body of the request.
+    fn with_digest_header(&mut self, body: &[u8], size: ShaSize) -> &mut Self;
+}
+
+/// `WithDate` defines a trait for adding a Date header to another library's request object.
+///
+/// Most verifiers require a signed Date header, so that old requests can't be replayed.
+pub trait WithDate {
+    /// Adds a Date header for `date`, formatted by `http_date`, unless the request already has a
+    /// Date header
+    fn with_date_header_if_missing(&mut self, date: SystemTime) -> &mut Self;
+}
+
+/// The `Clock` trait is where signing and verification read the current time from.
+///
+/// `SystemClock` reads the system clock, and is used unless another clock is given. A
+/// `SystemTime` is a `Clock` that always returns itself, so tests can sign and verify at a fixed
+/// time.
+///
+/// ### Example
+/// ```rust
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use http_signatures::prelude::*;
+///
+/// let fixed = UNIX_EPOCH + Duration::from_secs(1402174295);
+///
+/// assert_eq!(fixed.now(), fixed);
+/// ```
+pub trait Clock {
+    /// The current time
+    fn now(&self) -> SystemTime;
+}
+
+/// The `Clock` backed by `SystemTime::now`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+impl Clock for SystemTime {
+    fn now(&self) -> SystemTime {
+        *self
+    }
 }
 
 /// The `GetKey` trait is used during HTTP Signature verification to access the required decryption
 /// key based on a given `key_id`.
 ///
 /// The `key_id` is provided in the Authorization or Signature header of the request as `KeyId`.
+/// Verification parses the header first, then asks the `GetKey` type for the key matching its
+/// `key_id`, so keys can be fetched from a database, a remote server, or a cache as needed.
 ///
 /// ### Example
 /// ```rust
@@ -130,6 +597,179 @@ pub trait GetKey {
     type Error;
 
     fn get_key(self, key_id: &str) -> Result<Self::Key, Self::Error>;
+
+    /// Get every key that may have signed a request with the given `key_id`
+    ///
+    /// While a key is being rotated, requests signed with either the old or the new key should be
+    /// accepted. Verification succeeds if any of the returned keys verifies the signature. By
+    /// default, this returns the key from `get_key`.
+    fn get_keys(self, key_id: &str) -> Result<Vec<Self::Key>, Self::Error>
+    where
+        Self: Sized,
+    {
+        self.get_key(key_id).map(|key| vec![key])
+    }
+
+    /// The algorithm the keys for `key_id` are meant for, if the key getter knows it
+    ///
+    /// The verifier checks this before getting any keys, and fails with
+    /// `VerificationError::AlgorithmMismatch` if the signature claims another algorithm. Key
+    /// getters that know which algorithm each key is for should return it, so that a public key is
+    /// never used as an HMAC secret. By default, this returns `None`.
+    fn key_algorithm(&self, _key_id: &str) -> Option<SignatureAlgorithm> {
+        None
+    }
+}
+
+/// A borrowed `HashMap` of key ids to keys can be used directly as a `GetKey` type, which is
+/// enough for tests and deployments with a fixed set of keys.
+///
+/// ### Example
+/// ```rust
+/// # use std::collections::HashMap;
+/// use http_signatures::prelude::*;
+///
+/// let mut keys = HashMap::new();
+/// keys.insert("key-1".to_owned(), vec![1, 2, 3, 4, 5]);
+///
+/// assert!((&keys).get_key("key-1").is_ok());
+/// assert!((&keys).get_key("key-2").is_err());
+/// ```
+impl<'a> GetKey for &'a HashMap<String, Vec<u8>> {
+    type Key = &'a [u8];
+    type Error = VerificationError;
+
+    fn get_key(self, key_id: &str) -> Result<Self::Key, Self::Error> {
+        self.get(key_id)
+            .map(|key| key.as_slice())
+            .ok_or(VerificationError::GetKey)
+    }
+}
+
+/// A borrowed `HashMap` of key ids to several keys can be used as a `GetKey` type while keys are
+/// rotated. A request verifies if it was signed with any of the keys for its key id.
+///
+/// ### Example
+/// ```rust
+/// # use std::collections::HashMap;
+/// use http_signatures::prelude::*;
+///
+/// let mut keys = HashMap::new();
+/// keys.insert("key-1".to_owned(), vec![vec![1, 2, 3], vec![4, 5, 6]]);
+///
+/// assert_eq!((&keys).get_keys("key-1").unwrap().len(), 2);
+/// assert!((&keys).get_keys("key-2").is_err());
+/// ```
+impl<'a> GetKey for &'a HashMap<String, Vec<Vec<u8>>> {
+    type Key = &'a [u8];
+    type Error = VerificationError;
+
+    fn get_key(self, key_id: &str) -> Result<Self::Key, Self::Error> {
+        self.get(key_id)
+            .and_then(|keys| keys.first())
+            .map(|key| key.as_slice())
+            .ok_or(VerificationError::GetKey)
+    }
+
+    fn get_keys(self, key_id: &str) -> Result<Vec<Self::Key>, Self::Error> {
+        self.get(key_id)
+            .map(|keys| keys.iter().map(|key| key.as_slice()).collect())
+            .ok_or(VerificationError::GetKey)
+    }
+}
+
+/// A borrowed `HashMap` of key ids to `PublicKey`s can be used as a `GetKey` type. Signatures
+/// that claim a different algorithm than the key was parsed for are rejected.
+///
+/// ### Example
+/// ```rust
+/// # use std::collections::HashMap;
+/// use http_signatures::prelude::*;
+/// use http_signatures::{PublicKey, SignatureAlgorithm};
+///
+/// let mut keys = HashMap::new();
+/// keys.insert("key-1".to_owned(), PublicKey::from_ed25519_bytes(&[7; 32]).unwrap());
+///
+/// assert_eq!((&keys).key_algorithm("key-1").unwrap().to_string(), "ed25519");
+/// assert!((&keys).get_key("key-1").is_ok());
+/// ```
+impl<'a> GetKey for &'a HashMap<String, PublicKey> {
+    type Key = &'a [u8];
+    type Error = VerificationError;
+
+    fn get_key(self, key_id: &str) -> Result<Self::Key, Self::Error> {
+        self.get(key_id)
+            .map(|key| key.as_bytes())
+            .ok_or(VerificationError::GetKey)
+    }
+
+    fn key_algorithm(&self, key_id: &str) -> Option<SignatureAlgorithm> {
+        self.get(key_id).map(|key| key.algorithm().clone())
+    }
+}
+
+/// The `HeaderSource` trait is implemented by types holding the headers of a request, so that
+/// `http_signatures::verify` can read them.
+///
+/// Implementing it for another library's header type is enough to verify that library's requests.
+///
+/// ### Example
+/// ```rust
+/// use http_signatures::prelude::*;
+/// use http_signatures::VerificationError;
+///
+/// struct MyHeaders {
+///     headers: Vec<(String, String)>,
+/// }
+///
+/// impl HeaderSource for MyHeaders {
+///     fn header_pairs(&self) -> Result<Vec<(&str, &str)>, VerificationError> {
+///         Ok(self.headers
+///             .iter()
+///             .map(|&(ref name, ref value)| (name.as_str(), value.as_str()))
+///             .collect())
+///     }
+/// }
+/// ```
+pub trait HeaderSource {
+    /// Get every header as a name and value. A header with several values may appear once for
+    /// each value.
+    fn header_pairs(&self) -> Result<Vec<(&str, &str)>, VerificationError>;
+}
+
+impl<'a> HeaderSource for [(&'a str, &'a str)] {
+    fn header_pairs(&self) -> Result<Vec<(&str, &str)>, VerificationError> {
+        Ok(self.to_vec())
+    }
+}
+
+impl HeaderSource for [(String, String)] {
+    fn header_pairs(&self) -> Result<Vec<(&str, &str)>, VerificationError> {
+        Ok(self.iter()
+            .map(|&(ref name, ref value)| (name.as_str(), value.as_str()))
+            .collect())
+    }
+}
+
+impl<T> HeaderSource for Vec<T>
+where
+    [T]: HeaderSource,
+{
+    fn header_pairs(&self) -> Result<Vec<(&str, &str)>, VerificationError> {
+        self.as_slice().header_pairs()
+    }
+}
+
+impl HeaderSource for BTreeMap<String, Vec<String>> {
+    fn header_pairs(&self) -> Result<Vec<(&str, &str)>, VerificationError> {
+        Ok(self.iter()
+            .flat_map(|(name, values)| {
+                values
+                    .iter()
+                    .map(move |value| (name.as_str(), value.as_str()))
+            })
+            .collect())
+    }
 }
 
 /// The `VerifyHeader` trait is meant to be implemented for the request types from
@@ -141,10 +781,13 @@ pub trait GetKey {
 /// [hyper server](https://github.com/asonix/http-signatures/blob/master/examples/hyper_server.rs)
 /// and [rocket](https://github.com/asonix/http-signatures/blob/master/examples/rocket.rs) files.
 pub trait VerifyHeader {
-    fn verify_signature_header<G: GetKey>(&self, key_getter: G) -> Result<(), VerificationError>;
+    fn verify_signature_header<G: GetKey>(
+        &self,
+        key_getter: G,
+    ) -> Result<Verification, VerificationError>;
 
     fn verify_authorization_header<G: GetKey>(
         &self,
         key_getter: G,
-    ) -> Result<(), VerificationError>;
+    ) -> Result<Verification, VerificationError>;
 }
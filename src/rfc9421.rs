@@ -0,0 +1,537 @@
+// This file is part of HTTP Signatures
+
+// HTTP Signatures is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// HTTP Signatures is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
+
+//! Available with the `rfc9421` feature. This module creates signatures in the format of
+//! [RFC 9421](https://www.rfc-editor.org/rfc/rfc9421), HTTP Message Signatures.
+//!
+//! RFC 9421 is the standardized successor of the draft the rest of this crate implements. Instead
+//! of a single Authorization or Signature header, it uses a `Signature-Input` header describing
+//! what was signed, and a `Signature` header holding the signature itself. The signing string,
+//! called the signature base, is built from component identifiers such as `"@method"`,
+//! `"@target-uri"`, and lowercased header names.
+//!
+//! Signing is done with a `Signer`, such as a `SigningKey`, so keys are shared with the rest of the
+//! crate. The `alg` parameter is not included in `Signature-Input`, since the verifier is expected
+//! to know the algorithm of the key named by `keyid`.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! # extern crate http_signatures;
+//! #
+//! # use std::io::Cursor;
+//! #
+//! # use http_signatures::{Error, SigningKey, ShaSize, SignatureAlgorithm};
+//! use http_signatures::rfc9421::MessageSignature;
+//!
+//! # fn run() -> Result<(), Error> {
+//! let alg = SignatureAlgorithm::HMAC(ShaSize::TwoFiftySix);
+//! let key = SigningKey::new("hmac-key-1".into(), Cursor::new(b"secret".to_vec()), alg)?;
+//!
+//! let headers = MessageSignature::new("sig1")
+//!     .component("@method", "POST")
+//!     .component("@target-uri", "https://example.com/foo")
+//!     .component("Content-Type", "application/json")
+//!     .with_created(1618884473)
+//!     .sign(&key)?;
+//!
+//! assert_eq!(
+//!     headers.signature_input,
+//!     r#"sig1=("@method" "@target-uri" "content-type");created=1618884473;keyid="hmac-key-1""#
+//! );
+//! assert!(headers.signature.starts_with("sig1=:"));
+//! #     Ok(())
+//! # }
+//! # fn main() {
+//! #     run().unwrap();
+//! # }
+//! ```
+
+use base64::encode;
+
+use digest::Digest;
+use error::{CreationError, Error};
+use key::check_key_id;
+use prelude::Signer;
+use super::unfold_header_value;
+#[cfg(feature = "use_http")]
+use ShaSize;
+
+/// The component identifier of the signature parameters, which is always the last line of the
+/// signature base.
+pub const SIGNATURE_PARAMS: &str = "@signature-params";
+
+/// The name of the RFC 9530 Content-Digest header
+pub const CONTENT_DIGEST: &str = "content-digest";
+
+/// The `MessageSignature` struct describes an RFC 9421 signature: its label, the components it
+/// covers, and its timestamps.
+///
+/// Components are signed in the order they are added. Component names starting with `@` are
+/// derived components, such as `@method` or `@authority`, and are kept as they are. Other names
+/// are header names, and are lowercased.
+#[derive(Clone, Debug)]
+pub struct MessageSignature {
+    label: String,
+    components: Vec<(String, String)>,
+    created: Option<u64>,
+    expires: Option<u64>,
+}
+
+/// The headers produced by signing a `MessageSignature`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureHeaders {
+    /// The value of the `Signature-Input` header
+    pub signature_input: String,
+    /// The value of the `Signature` header
+    pub signature: String,
+}
+
+impl MessageSignature {
+    /// Start a signature with the given label, such as `sig1`.
+    pub fn new<S: Into<String>>(label: S) -> Self {
+        MessageSignature {
+            label: label.into(),
+            components: Vec::new(),
+            created: None,
+            expires: None,
+        }
+    }
+
+    /// Add a component to the signature. Header values are trimmed and unfolded, and several
+    /// values for one header should be joined with ", " before being added.
+    pub fn component<S: Into<String>>(mut self, name: &str, value: S) -> Self {
+        let name = if name.starts_with('@') {
+            name.to_owned()
+        } else {
+            name.to_lowercase()
+        };
+
+        self.components.push((name, unfold_header_value(&value.into())));
+        self
+    }
+
+    /// Add a `content-digest` component holding the RFC 9530 Content-Digest value of the given
+    /// digest. The same value must be sent as the request's Content-Digest header.
+    ///
+    /// This Errors with `UnsupportedDigest` for SHA-384 digests, which RFC 9530 doesn't define.
+    pub fn with_content_digest(self, digest: &Digest) -> Result<Self, CreationError> {
+        let value = digest
+            .content_digest_value()
+            .ok_or(CreationError::UnsupportedDigest)?;
+
+        Ok(self.component(CONTENT_DIGEST, value))
+    }
+
+    /// Add a `created` parameter, in seconds since the Unix epoch
+    ///
+    /// RFC 9421 defines `created` and `expires` as structured field Integers, so they are always
+    /// whole seconds. A Decimal such as `1618884473.5` isn't a valid value, and conforming
+    /// verifiers reject it, so sub-second timestamps can't be signed.
+    pub fn with_created(mut self, created: u64) -> Self {
+        self.created = Some(created);
+        self
+    }
+
+    /// Add an `expires` parameter, in seconds since the Unix epoch
+    pub fn with_expires(mut self, expires: u64) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The component names, in the order they are signed
+    pub fn components(&self) -> Vec<&str> {
+        self.components
+            .iter()
+            .map(|&(ref name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Build the signature parameters, which are both the value of `@signature-params` and,
+    /// after the label, the value of the `Signature-Input` header.
+    pub fn signature_params(&self, key_id: &str) -> String {
+        let components: Vec<String> = self.components
+            .iter()
+            .map(|&(ref name, _)| format!("\"{}\"", name))
+            .collect();
+
+        let mut params = format!("({})", components.join(" "));
+
+        if let Some(created) = self.created {
+            params.push_str(&format!(";created={}", created));
+        }
+        if let Some(expires) = self.expires {
+            params.push_str(&format!(";expires={}", expires));
+        }
+        params.push_str(&format!(";keyid=\"{}\"", key_id));
+
+        params
+    }
+
+    /// Build the signature base, the string that is signed.
+    ///
+    /// This Errors if a component value contains a newline, or if the key id contains a quote,
+    /// since neither can be represented.
+    pub fn signature_base(&self, key_id: &str) -> Result<String, CreationError> {
+        check_key_id(key_id)?;
+
+        let mut lines = Vec::new();
+
+        for &(ref name, ref value) in self.components.iter() {
+            if value.contains('\n') || value.contains('\r') {
+                return Err(CreationError::InvalidHeaderValue);
+            }
+
+            lines.push(format!("\"{}\": {}", name, value));
+        }
+
+        lines.push(format!(
+            "\"{}\": {}",
+            SIGNATURE_PARAMS,
+            self.signature_params(key_id)
+        ));
+
+        Ok(lines.join("\n"))
+    }
+
+    /// Sign the signature base with the given key, producing the `Signature-Input` and
+    /// `Signature` headers.
+    pub fn sign<S: Signer>(&self, key: &S) -> Result<SignatureHeaders, Error> {
+        if let (Some(created), Some(expires)) = (self.created, self.expires) {
+            if expires < created {
+                return Err(CreationError::ExpiresBeforeCreated.into());
+            }
+        }
+
+        let signature_base = self.signature_base(key.key_id())?;
+        let signature = key.sign(signature_base.as_bytes())?;
+
+        Ok(SignatureHeaders {
+            signature_input: format!(
+                "{}={}",
+                self.label,
+                self.signature_params(key.key_id())
+            ),
+            signature: format!("{}=:{}:", self.label, encode(&signature)),
+        })
+    }
+}
+
+/// Sign an `http::Request`, adding `Signature-Input` and `Signature` headers.
+///
+/// The derived components `@method`, `@target-uri`, `@authority`, `@scheme`, `@request-target`,
+/// `@path`, and `@query` are read from the request, and any other names from its headers. This
+/// Errors with `MissingHeaders` if a named component isn't present, which for `@target-uri`,
+/// `@authority`, and `@scheme` means the request's URI must be absolute.
+#[cfg(feature = "use_http")]
+pub fn sign_request<B, S>(
+    req: &mut ::http::Request<B>,
+    signature: MessageSignature,
+    components: &[&str],
+    key: &S,
+) -> Result<(), Error>
+where
+    S: Signer,
+{
+    use http::header::{HeaderName, HeaderValue};
+
+    let mut signature = signature;
+    let mut missing = Vec::new();
+
+    for name in components {
+        let value = {
+            let uri = req.uri();
+
+            match *name {
+                "@method" => Some(req.method().as_str().to_owned()),
+                "@target-uri" => match uri.scheme_part() {
+                    Some(_) => Some(uri.to_string()),
+                    None => None,
+                },
+                "@authority" => uri.authority_part().map(|a| a.as_str().to_lowercase()),
+                "@scheme" => uri.scheme_part().map(|s| s.as_str().to_lowercase()),
+                "@request-target" => Some(
+                    uri.path_and_query()
+                        .map(|pq| pq.as_str().to_owned())
+                        .unwrap_or_else(|| "/".to_owned()),
+                ),
+                "@path" => Some(uri.path().to_owned()),
+                "@query" => Some(format!("?{}", uri.query().unwrap_or(""))),
+                name => {
+                    let values: Vec<String> = req.headers()
+                        .get_all(name.to_lowercase().as_str())
+                        .iter()
+                        .map(|value| String::from_utf8_lossy(value.as_bytes()))
+                        .map(|value| unfold_header_value(&value))
+                        .collect();
+
+                    if values.is_empty() {
+                        None
+                    } else {
+                        Some(values.join(", "))
+                    }
+                }
+            }
+        };
+
+        match value {
+            Some(value) => signature = signature.component(name, value),
+            None => missing.push(name.to_string()),
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(CreationError::MissingHeaders(missing.join(", ")).into());
+    }
+
+    let headers = signature.sign(key)?;
+
+    let signature_input = HeaderValue::from_str(&headers.signature_input)
+        .map_err(|_| CreationError::InvalidHeaderValue)?;
+    let signature = HeaderValue::from_str(&headers.signature)
+        .map_err(|_| CreationError::InvalidHeaderValue)?;
+
+    req.headers_mut()
+        .insert(HeaderName::from_static("signature-input"), signature_input);
+    req.headers_mut()
+        .insert(HeaderName::from_static("signature"), signature);
+
+    Ok(())
+}
+
+/// Hash the body of an `http::Request` and add it as a Content-Digest header, so that
+/// `content-digest` can be passed to `sign_request` as a component.
+///
+/// This Errors with `UnsupportedDigest` for `ShaSize::ThreeEightyFour`, which RFC 9530 doesn't
+/// define.
+#[cfg(feature = "use_http")]
+pub fn add_content_digest<B>(req: &mut ::http::Request<B>, size: ShaSize) -> Result<(), Error>
+where
+    B: AsRef<[u8]>,
+{
+    use http::header::{HeaderName, HeaderValue};
+
+    let value = Digest::from_body(req.body().as_ref(), size)
+        .content_digest_value()
+        .ok_or(CreationError::UnsupportedDigest)?;
+    let value = HeaderValue::from_str(&value).map_err(|_| CreationError::InvalidHeaderValue)?;
+
+    req.headers_mut()
+        .insert(HeaderName::from_static(CONTENT_DIGEST), value);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use base64::decode;
+
+    use digest::Digest;
+    use error::{CreationError, Error};
+    use key::SigningKey;
+    use super::MessageSignature;
+    use ShaSize;
+    use SignatureAlgorithm;
+
+    // The shared secret from RFC 9421, Appendix B.1.4
+    const SHARED_SECRET: &str = "uzvJfB4u3N0Jy4T7NZ75MDVcr8zSTInedJtkgcu46YW4XByzNJjxBdtjUkdJPBtbm\
+                                 HhIDi6pcl8jsasjlTMtDQ==";
+
+    fn shared_secret_key() -> SigningKey {
+        SigningKey::new(
+            "test-shared-secret".into(),
+            Cursor::new(decode(SHARED_SECRET).unwrap()),
+            SignatureAlgorithm::HMAC(ShaSize::TwoFiftySix),
+        ).unwrap()
+    }
+
+    fn rfc_signature() -> MessageSignature {
+        MessageSignature::new("sig-b25")
+            .component("Date", "Tue, 20 Apr 2021 02:07:55 GMT")
+            .component("@authority", "example.com")
+            .component("Content-Type", "application/json")
+            .with_created(1618884473)
+    }
+
+    #[test]
+    fn builds_signature_base() {
+        assert_eq!(
+            rfc_signature().signature_base("test-shared-secret").unwrap(),
+            concat!(
+                r#""date": Tue, 20 Apr 2021 02:07:55 GMT"#,
+                "\n",
+                r#""@authority": example.com"#,
+                "\n",
+                r#""content-type": application/json"#,
+                "\n",
+                r#""@signature-params": ("date" "@authority" "content-type")"#,
+                r#";created=1618884473;keyid="test-shared-secret""#
+            )
+        );
+    }
+
+    #[test]
+    fn timestamps_are_integers() {
+        let signature = MessageSignature::new("sig1")
+            .component("@method", "GET")
+            .with_created(1618884473)
+            .with_expires(1618884773);
+
+        assert_eq!(
+            signature.signature_params("test-shared-secret"),
+            concat!(
+                r#"("@method");created=1618884473;expires=1618884773"#,
+                r#";keyid="test-shared-secret""#
+            )
+        );
+    }
+
+    #[test]
+    fn matches_rfc_hmac_example() {
+        // RFC 9421, Appendix B.2.5
+        let headers = rfc_signature().sign(&shared_secret_key()).unwrap();
+
+        assert_eq!(
+            headers.signature_input,
+            concat!(
+                r#"sig-b25=("date" "@authority" "content-type")"#,
+                r#";created=1618884473;keyid="test-shared-secret""#
+            )
+        );
+        assert_eq!(
+            headers.signature,
+            "sig-b25=:pxcQw6G3AjtMBQjwo8XzkZf/bws5LelbaMk5rGIGtE8=:"
+        );
+    }
+
+    #[test]
+    fn rejects_newlines_in_components() {
+        let res = MessageSignature::new("sig1")
+            .component("x-example", "line one\nline two")
+            .sign(&shared_secret_key());
+
+        match res {
+            Err(Error::Creation(CreationError::InvalidHeaderValue)) => (),
+            res => panic!("Expected InvalidHeaderValue, got {:?}", res),
+        }
+    }
+
+    #[cfg(feature = "use_http")]
+    #[test]
+    fn signs_http_request() {
+        use http::Request;
+
+        let mut req = Request::post("https://example.com/foo?param=Value")
+            .header("content-type", "application/json")
+            .body(())
+            .unwrap();
+
+        super::sign_request(
+            &mut req,
+            MessageSignature::new("sig1").with_created(1618884473),
+            &["@method", "@target-uri", "@authority", "content-type"],
+            &shared_secret_key(),
+        ).unwrap();
+
+        assert_eq!(
+            req.headers().get("signature-input").unwrap(),
+            concat!(
+                r#"sig1=("@method" "@target-uri" "@authority" "content-type")"#,
+                r#";created=1618884473;keyid="test-shared-secret""#
+            )
+        );
+        assert!(req.headers().get("signature").is_some());
+
+        let res = super::sign_request(
+            &mut req,
+            MessageSignature::new("sig2"),
+            &["date"],
+            &shared_secret_key(),
+        );
+
+        match res {
+            Err(Error::Creation(CreationError::MissingHeaders(missing))) => {
+                assert_eq!(missing, "date")
+            }
+            res => panic!("Expected MissingHeaders, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn signs_content_digest() {
+        let body = br#"{"hello": "world"}"#;
+
+        let signature = MessageSignature::new("sig1")
+            .with_content_digest(&Digest::from_body(body, ShaSize::TwoFiftySix))
+            .unwrap();
+
+        assert_eq!(signature.components(), vec!["content-digest"]);
+        assert!(
+            signature
+                .signature_base("test-shared-secret")
+                .unwrap()
+                .starts_with(concat!(
+                    r#""content-digest": "#,
+                    "sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:\n"
+                ))
+        );
+
+        let res = MessageSignature::new("sig1")
+            .with_content_digest(&Digest::from_body(body, ShaSize::ThreeEightyFour));
+
+        match res {
+            Err(CreationError::UnsupportedDigest) => (),
+            res => panic!("Expected UnsupportedDigest, got {:?}", res),
+        }
+    }
+
+    #[cfg(feature = "use_http")]
+    #[test]
+    fn adds_content_digest_to_http_request() {
+        use http::Request;
+
+        let mut req = Request::post("https://example.com/foo")
+            .body(br#"{"hello": "world"}"#.to_vec())
+            .unwrap();
+
+        super::add_content_digest(&mut req, ShaSize::FiveTwelve).unwrap();
+
+        assert_eq!(
+            req.headers().get("content-digest").unwrap(),
+            concat!(
+                "sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrI",
+                "iYllu7BNNyealdVLvRwEmTHWXvJwew==:"
+            )
+        );
+
+        super::sign_request(
+            &mut req,
+            MessageSignature::new("sig1"),
+            &["@method", "content-digest"],
+            &shared_secret_key(),
+        ).unwrap();
+
+        assert_eq!(
+            req.headers().get("signature-input").unwrap(),
+            r#"sig1=("@method" "content-digest");keyid="test-shared-secret""#
+        );
+    }
+}
@@ -0,0 +1,120 @@
+// This file is part of HTTP Signatures
+
+// HTTP Signatures is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// HTTP Signatures is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module emits `tracing` spans and events when the `tracing` feature is enabled, and does
+//! nothing otherwise.
+//!
+//! Spans and events carry the key id and algorithm, but never key material or signatures.
+
+#[cfg(feature = "tracing")]
+mod imp {
+    use tracing::{field, Level};
+
+    use error::{CreationError, VerificationError};
+    use SignatureAlgorithm;
+
+    const TARGET: &str = "http_signatures";
+
+    // Spans are returned rather than entered, since an entered span borrows the span it enters.
+    // Callers keep the span alongside its guard, as in `let _guard = span.enter();`.
+    pub(crate) type Span = ::tracing::Span;
+
+    pub(crate) fn parse_key_span(algorithm: &SignatureAlgorithm) -> Span {
+        span!(
+            target: TARGET,
+            Level::DEBUG,
+            "parse_key",
+            algorithm = field::display(algorithm)
+        )
+    }
+
+    pub(crate) fn sign_span(key_id: &str, algorithm: &SignatureAlgorithm) -> Span {
+        span!(
+            target: TARGET,
+            Level::DEBUG,
+            "sign",
+            key_id = field::display(key_id),
+            algorithm = field::display(algorithm)
+        )
+    }
+
+    pub(crate) fn verify_span(key_id: &str, algorithm: &SignatureAlgorithm) -> Span {
+        span!(
+            target: TARGET,
+            Level::DEBUG,
+            "verify",
+            key_id = field::display(key_id),
+            algorithm = field::display(algorithm)
+        )
+    }
+
+    pub(crate) fn creation<T>(result: &Result<T, CreationError>, action: &str) {
+        match *result {
+            Ok(_) => debug!(target: TARGET, "{} succeeded", action),
+            Err(ref e) => debug!(target: TARGET, error = field::display(e), "{} failed", action),
+        }
+    }
+
+    pub(crate) fn weak_headers(key_id: &str) {
+        warn!(
+            target: TARGET,
+            key_id = field::display(key_id),
+            "none of (request-target), date and (created) are signed"
+        );
+    }
+
+    pub(crate) fn verification<T>(result: &Result<T, VerificationError>) {
+        match *result {
+            Ok(_) => debug!(target: TARGET, "signature verified"),
+            Err(ref e) => warn!(target: TARGET, error = field::display(e), "verification failed"),
+        }
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+mod imp {
+    use error::{CreationError, VerificationError};
+    use SignatureAlgorithm;
+
+    pub(crate) struct Span;
+
+    pub(crate) struct Guard;
+
+    impl Span {
+        pub(crate) fn enter(&self) -> Guard {
+            Guard
+        }
+    }
+
+    pub(crate) fn parse_key_span(_: &SignatureAlgorithm) -> Span {
+        Span
+    }
+
+    pub(crate) fn sign_span(_: &str, _: &SignatureAlgorithm) -> Span {
+        Span
+    }
+
+    pub(crate) fn verify_span(_: &str, _: &SignatureAlgorithm) -> Span {
+        Span
+    }
+
+    pub(crate) fn creation<T>(_: &Result<T, CreationError>, _: &str) {}
+
+    pub(crate) fn weak_headers(_: &str) {}
+
+    pub(crate) fn verification<T>(_: &Result<T, VerificationError>) {}
+}
+
+pub(crate) use self::imp::*;
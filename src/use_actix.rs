@@ -0,0 +1,239 @@
+// This file is part of HTTP Signatures
+
+// HTTP Signatures is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// HTTP Signatures is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
+
+//! Available with the `use_actix` feature. This module defines the `SignedRequest` extractor for
+//! actix-web.
+//!
+//! A handler taking a `SignedRequest` only runs if the request carries a valid HTTP Signature,
+//! in either the Authorization or the Signature header. Requests that don't are rejected with
+//! `401 Unauthorized`. An Authorization header with other credentials, such as a Bearer token, is
+//! skipped in favour of the Signature header.
+//!
+//! Since the key used to verify a request depends on its `keyId`, the extractor must be given a
+//! key resolver, which maps a `keyId` to the bytes of its public key (or shared secret, for
+//! HMAC).
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! # extern crate actix_web;
+//! # extern crate http_signatures;
+//! use actix_web::{http, App};
+//! use http_signatures::use_actix::SignedRequest;
+//!
+//! fn index(signed: SignedRequest) -> String {
+//!     format!("Hello, {}", signed.key_id())
+//! }
+//!
+//! let app = App::new().resource("/", |r| {
+//!     r.method(http::Method::GET).with_config(index, |cfg| {
+//!         cfg.0.key_resolver(|key_id| lookup_public_key(key_id));
+//!     })
+//! });
+//! ```
+
+use std::io::Cursor;
+use std::rc::Rc;
+use std::str::from_utf8;
+
+use actix_web::{FromRequest, HttpRequest};
+use actix_web::error::{Error as ActixError, ErrorUnauthorized};
+
+use error::VerificationError;
+use prelude::*;
+use use_http::signature_header;
+use verify::{verify, Verification};
+
+/// The `SignedRequest` extractor verifies the HTTP Signature of a request.
+///
+/// On success, it holds the details of the signature, including the `keyId` of the key the
+/// request was signed with.
+#[derive(Clone, Debug)]
+pub struct SignedRequest {
+    verification: Verification,
+}
+
+impl SignedRequest {
+    /// The `keyId` of the key that signed the request
+    pub fn key_id(&self) -> &str {
+        self.verification.key_id()
+    }
+
+    /// The details of the signature, such as which headers it covers
+    pub fn verification(&self) -> &Verification {
+        &self.verification
+    }
+}
+
+/// Configuration for the `SignedRequest` extractor, which holds the key resolver.
+///
+/// Without a key resolver, every request is rejected.
+#[derive(Clone, Default)]
+pub struct SignedRequestConfig {
+    resolver: Option<Rc<Fn(&str) -> Option<Vec<u8>>>>,
+}
+
+impl SignedRequestConfig {
+    /// Set the function used to look up the key for a `keyId`. Returning `None` rejects the
+    /// request.
+    pub fn key_resolver<F>(&mut self, resolver: F) -> &mut Self
+    where
+        F: Fn(&str) -> Option<Vec<u8>> + 'static,
+    {
+        self.resolver = Some(Rc::new(resolver));
+        self
+    }
+}
+
+impl<S> FromRequest<S> for SignedRequest {
+    type Config = SignedRequestConfig;
+    type Result = Result<Self, ActixError>;
+
+    fn from_request(req: &HttpRequest<S>, cfg: &Self::Config) -> Self::Result {
+        let resolver = cfg.resolver
+            .clone()
+            .ok_or(ErrorUnauthorized(VerificationError::GetKey))?;
+
+        verify_request(req, resolver).map_err(ErrorUnauthorized)
+    }
+}
+
+struct ResolverKeyGetter {
+    resolver: Rc<Fn(&str) -> Option<Vec<u8>>>,
+}
+
+impl GetKey for ResolverKeyGetter {
+    type Key = Cursor<Vec<u8>>;
+    type Error = VerificationError;
+
+    fn get_key(self, key_id: &str) -> Result<Self::Key, Self::Error> {
+        (self.resolver)(key_id)
+            .map(Cursor::new)
+            .ok_or(VerificationError::GetKey)
+    }
+}
+
+fn verify_request<S>(
+    req: &HttpRequest<S>,
+    resolver: Rc<Fn(&str) -> Option<Vec<u8>>>,
+) -> Result<SignedRequest, VerificationError> {
+    let header = signature_header(req.headers()).ok_or(VerificationError::HeaderNotPresent)?;
+
+    let query = req.query_string();
+
+    let verification = verify(
+        req.method().as_str(),
+        req.path(),
+        if query.is_empty() { None } else { Some(query) },
+        req.headers(),
+        from_utf8(header.as_bytes())?,
+        ResolverKeyGetter { resolver },
+    )?;
+
+    Ok(SignedRequest { verification })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use actix_web::FromRequest;
+    use actix_web::http::Method;
+    use actix_web::test::TestRequest;
+
+    use create::HttpSignature;
+    use super::{SignedRequest, SignedRequestConfig};
+    use ShaSize;
+    use SignatureAlgorithm;
+    use REQUEST_TARGET;
+
+    const KEY: &[u8] = b"shared secret";
+    const DATE: &str = "Tue, 07 Jun 2014 20:51:35 GMT";
+
+    fn signature(path: &str) -> HttpSignature<&'static [u8]> {
+        let mut headers = BTreeMap::new();
+        headers.insert(REQUEST_TARGET.into(), vec![format!("get {}", path)]);
+        headers.insert("date".into(), vec![DATE.into()]);
+
+        HttpSignature::new(
+            "hmac-key-1".into(),
+            KEY,
+            SignatureAlgorithm::HMAC(ShaSize::TwoFiftySix),
+            headers,
+        ).unwrap()
+    }
+
+    fn auth_header(path: &str) -> String {
+        signature(path).authorization_header().unwrap()
+    }
+
+    fn config() -> SignedRequestConfig {
+        let mut cfg = SignedRequestConfig::default();
+        cfg.key_resolver(|key_id| {
+            if key_id == "hmac-key-1" {
+                Some(KEY.to_vec())
+            } else {
+                None
+            }
+        });
+        cfg
+    }
+
+    #[test]
+    fn accepts_signed_request() {
+        let req = TestRequest::with_header("date", DATE)
+            .method(Method::GET)
+            .uri("/foo")
+            .header("authorization", auth_header("/foo"))
+            .finish();
+
+        let signed = SignedRequest::from_request(&req, &config()).unwrap();
+
+        assert_eq!(signed.key_id(), "hmac-key-1");
+        assert!(signed.verification().covers("date"));
+    }
+
+    #[test]
+    fn bearer_token_does_not_hide_signature_header() {
+        let req = TestRequest::with_header("date", DATE)
+            .method(Method::GET)
+            .uri("/foo")
+            .header("authorization", "Bearer abc")
+            .header("signature", signature("/foo").signature_header().unwrap())
+            .finish();
+
+        let signed = SignedRequest::from_request(&req, &config()).unwrap();
+
+        assert_eq!(signed.key_id(), "hmac-key-1");
+    }
+
+    #[test]
+    fn rejects_tampered_request() {
+        let req = TestRequest::with_header("date", DATE)
+            .method(Method::GET)
+            .uri("/bar")
+            .header("authorization", auth_header("/foo"))
+            .finish();
+
+        assert!(SignedRequest::from_request(&req, &config()).is_err());
+    }
+
+    #[test]
+    fn rejects_unsigned_request() {
+        let req = TestRequest::default().uri("/foo").finish();
+
+        assert!(SignedRequest::from_request(&req, &config()).is_err());
+    }
+}
@@ -0,0 +1,962 @@
+// This file is part of HTTP Signatures
+
+// HTTP Signatures is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// HTTP Signatures is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
+
+//! Available with the `use_http` feature. This module defines `AsHttpSignature`,
+//! `WithHttpSignature`, `WithDigest`, and `VerifyHeader` for `http::Request`, and
+//! `AsHttpSignature` and `VerifyHeader` for `http::Response`.
+//!
+//! The `http` crate's `Request` type is used by hyper 0.12 and later, and by the libraries built
+//! on top of it, so this can be used both to sign outgoing requests and to verify incoming ones.
+//!
+//! Responses are signed the same way as requests, except that the `(status)` pseudo-header,
+//! holding the three-digit status code, is signed in place of `(request-target)`. A server signs
+//! a response by adding the Signature header from `signature_header`, and a client checks it with
+//! `verify_signature_header`. Since nothing in a response ties it to the request it answers, a
+//! server that wants to prevent responses being swapped should include a header echoing the
+//! request, such as a request id, in the signature.
+//!
+//! # Example adding a signature to a Request type
+//!
+//! This example adds the HTTP Signature to the request directly as an Authorization header.
+//! `with_signature_header` can be used to add the signature as a Signature header instead.
+//!
+//! ```rust
+//! # extern crate http;
+//! # extern crate http_signatures;
+//! #
+//! # use std::error::Error;
+//! # use std::fs::File;
+//! #
+//! # use http_signatures::prelude::*;
+//! # use http_signatures::{ShaSize, SignatureAlgorithm};
+//! # use http::{Request, Response};
+//! #
+//! # fn run() -> Result<(), Box<Error>> {
+//! let key = File::open("tests/assets/private.der")?;
+//! let alg = SignatureAlgorithm::RSA(ShaSize::TwoFiftySix);
+//!
+//! let mut req = Request::post("https://example.com").body(())?;
+//!
+//! req.with_authorization_header("rsa-key-1".into(), key, alg)?;
+//! #     Ok(())
+//! # }
+//! # fn main() {
+//! #     run().unwrap();
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::str::from_utf8;
+use std::time::SystemTime;
+
+use http::{Request, Response};
+use http::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, DATE};
+
+use create::{HttpSignature, SignatureParams};
+use digest::Digest;
+use error::{CreationError, Error, VerificationError};
+use prelude::*;
+use prelude::signature_params_for;
+use verify::{is_signature_scheme, SignedHeader, Verification};
+use super::{absolute_request_target, absolute_uri, check_authorization, http_date, request_target,
+            sorted_query, ShaSize, SignatureAlgorithm, REQUEST_TARGET, STATUS};
+
+const SIGNATURE: &str = "signature";
+const DIGEST: &str = "digest";
+
+/// An implementation of `AsSignatureParams` for `http::Request`.
+///
+/// Headers with several values are signed as a single header, with the values joined in the
+/// order they appear in the request.
+impl<B> AsSignatureParams for Request<B> {
+    fn as_signature_params(
+        &self,
+        key_id: String,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<SignatureParams, Error> {
+        let mut headers = BTreeMap::new();
+        headers.insert(
+            REQUEST_TARGET.into(),
+            vec![request_target(self.method().as_str(), self.uri().path(), self.uri().query())],
+        );
+
+        let headers = self.headers()
+            .iter()
+            .fold(headers, |mut acc, (name, value)| {
+                acc.entry(name.as_str().into())
+                    .or_insert_with(Vec::new)
+                    .push(String::from_utf8_lossy(value.as_bytes()).into_owned());
+
+                acc
+            });
+
+        SignatureParams::new(key_id, algorithm, headers).map_err(Error::from)
+    }
+}
+
+/// An implementation of `AsHttpSignature` for `http::Request`, which signs the same headers as
+/// its `AsSignatureParams`.
+impl<T, B> AsHttpSignature<T> for Request<B>
+where
+    T: Read,
+{
+    fn as_http_signature(
+        &self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<HttpSignature<T>, Error> {
+        Ok(self.as_signature_params(key_id, algorithm)?.with_key(key))
+    }
+
+    fn as_http_signature_with_derived_host(
+        &self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<HttpSignature<T>, Error> {
+        let http_sig = self.as_http_signature(key_id, key, algorithm)?;
+
+        // The Host header never includes userinfo, which an authority may
+        let host = self.uri()
+            .authority_part()
+            .map(|authority| authority.as_str().rsplit('@').next().unwrap_or("").to_owned());
+
+        Ok(match host {
+            Some(host) => http_sig.with_header_if_missing("host", &host),
+            None => http_sig,
+        })
+    }
+
+    fn as_http_signature_absolute_form(
+        &self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<HttpSignature<T>, Error> {
+        let http_sig = self.as_http_signature(key_id, key, algorithm)?;
+
+        let uri = self.uri();
+
+        match (uri.scheme_part(), uri.host()) {
+            (Some(scheme), Some(host)) => {
+                let uri = absolute_uri(scheme.as_str(), host, uri.port(), uri.path(), uri.query());
+
+                Ok(http_sig
+                    .with_request_target(absolute_request_target(self.method().as_str(), &uri)))
+            }
+            _ => Ok(http_sig),
+        }
+    }
+
+    fn as_http_signature_with_sorted_query(
+        &self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<HttpSignature<T>, Error> {
+        let http_sig = self.as_http_signature(key_id, key, algorithm)?;
+
+        Ok(match self.uri().query() {
+            Some(query) => http_sig.with_request_target(request_target(
+                self.method().as_str(),
+                self.uri().path(),
+                Some(&sorted_query(query)),
+            )),
+            None => http_sig,
+        })
+    }
+
+    fn as_http_signature_in_request_order(
+        &self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<HttpSignature<T>, Error> {
+        let mut header_names = vec![REQUEST_TARGET];
+        header_names.extend(self.headers().keys().map(|name| name.as_str()));
+
+        self.as_http_signature_with_headers(key_id, key, algorithm, &header_names)
+    }
+}
+
+/// An implementation of `WithHttpSignature` for `http::Request`
+///
+/// This automatically adds an Authorization or Signature header to a given `http::Request`
+/// containing an HTTP Signature.
+impl<T, B> WithHttpSignature<T> for Request<B>
+where
+    T: Read,
+{
+    fn with_authorization_header(
+        &mut self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<&mut Self, Error> {
+        let auth_header = self.authorization_header(key_id, key, algorithm)?;
+
+        self.set_authorization_header(auth_header)
+    }
+
+    fn with_signature_header(
+        &mut self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<&mut Self, Error> {
+        let sig_header = self.signature_header(key_id, key, algorithm)?;
+
+        self.set_signature_header(sig_header)
+    }
+}
+
+/// An implementation of `WithSigningKey` for `http::Request`
+///
+/// This adds the same headers as `WithHttpSignature`, without reading a key.
+impl<B> WithSigningKey for Request<B> {
+    fn with_authorization_header_using<S: Signer>(
+        &mut self,
+        key: &S,
+    ) -> Result<&mut Self, Error> {
+        let auth_header = signature_params_for(&*self, key)?.authorization_header_using(key)?;
+
+        self.set_authorization_header(auth_header)
+    }
+
+    fn with_signature_header_using<S: Signer>(&mut self, key: &S) -> Result<&mut Self, Error> {
+        let sig_header = signature_params_for(&*self, key)?.signature_header_using(key)?;
+
+        self.set_signature_header(sig_header)
+    }
+}
+
+/// An implementation of `SetSignatureHeaders` for `http::Request`
+impl<B> SetSignatureHeaders for Request<B> {
+    fn set_authorization_header(&mut self, value: String) -> Result<&mut Self, Error> {
+        check_authorization(self.headers().get(AUTHORIZATION).map(HeaderValue::as_bytes))?;
+
+        let auth_header =
+            HeaderValue::from_str(&value).map_err(|_| CreationError::InvalidHeaderValue)?;
+
+        self.headers_mut().insert(AUTHORIZATION, auth_header);
+
+        Ok(self)
+    }
+
+    fn set_signature_header(&mut self, value: String) -> Result<&mut Self, Error> {
+        let sig_header =
+            HeaderValue::from_str(&value).map_err(|_| CreationError::InvalidHeaderValue)?;
+
+        self.headers_mut()
+            .insert(HeaderName::from_static(SIGNATURE), sig_header);
+
+        Ok(self)
+    }
+}
+
+/// The header holding the signature of a request: the Authorization header if it uses the
+/// `Signature` scheme, and otherwise the Signature header, so that other credentials, such as a
+/// Bearer token, don't hide a signature sent alongside them
+pub(crate) fn signature_header(headers: &HeaderMap) -> Option<&HeaderValue> {
+    match headers.get(AUTHORIZATION) {
+        Some(value) if value.to_str().map(is_signature_scheme).unwrap_or(false) => Some(value),
+        _ => headers.get(SIGNATURE),
+    }
+}
+
+impl<B> Resign for Request<B> {
+    fn has_signature_header(&self) -> bool {
+        self.headers().contains_key(SIGNATURE)
+    }
+
+    fn remove_signature_headers(&mut self) -> &mut Self {
+        let signed_authorization = self.headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .map(is_signature_scheme)
+            .unwrap_or(false);
+
+        if signed_authorization {
+            self.headers_mut().remove(AUTHORIZATION);
+        }
+        self.headers_mut().remove(SIGNATURE);
+
+        self
+    }
+}
+
+impl<B> WithDigest for Request<B> {
+    fn with_digest_header(&mut self, body: &[u8], size: ShaSize) -> &mut Self {
+        let digest = Digest::from_body(body, size);
+
+        // A Digest header is always an algorithm name and base64, which are valid header values
+        if let Ok(digest) = HeaderValue::from_str(&digest.to_string()) {
+            self.headers_mut()
+                .insert(HeaderName::from_static(DIGEST), digest);
+        }
+
+        self
+    }
+}
+
+impl<B> WithDate for Request<B> {
+    fn with_date_header_if_missing(&mut self, date: SystemTime) -> &mut Self {
+        if !self.headers().contains_key(DATE) {
+            // An HTTP date is always printable ASCII, which is a valid header value
+            if let Ok(date) = HeaderValue::from_str(&http_date(date)) {
+                self.headers_mut().insert(DATE, date);
+            }
+        }
+
+        self
+    }
+}
+
+impl<B> ReadBody for Request<B>
+where
+    B: AsRef<[u8]>,
+{
+    fn body_bytes(&self) -> &[u8] {
+        self.body().as_ref()
+    }
+}
+
+impl<B> VerifyHeader for Request<B> {
+    fn verify_signature_header<G: GetKey>(
+        &self,
+        key_getter: G,
+    ) -> Result<Verification, VerificationError> {
+        let sig_header = self.headers()
+            .get(SIGNATURE)
+            .ok_or(VerificationError::HeaderNotPresent)?;
+
+        verify_header(self, from_utf8(sig_header.as_bytes())?, key_getter)
+    }
+
+    fn verify_authorization_header<G: GetKey>(
+        &self,
+        key_getter: G,
+    ) -> Result<Verification, VerificationError> {
+        let auth_header = self.headers()
+            .get(AUTHORIZATION)
+            .ok_or(VerificationError::HeaderNotPresent)?;
+
+        verify_header(self, from_utf8(auth_header.as_bytes())?, key_getter)
+    }
+}
+
+fn verify_header<B, G>(
+    req: &Request<B>,
+    header: &str,
+    key_getter: G,
+) -> Result<Verification, VerificationError>
+where
+    G: GetKey,
+{
+    let header_pairs = req.headers().header_pairs()?;
+
+    SignedHeader::new(header)?.verify(
+        &header_pairs,
+        req.method().as_str(),
+        req.uri().path(),
+        req.uri().query(),
+        key_getter,
+    )
+}
+
+/// An implementation of `AsHttpSignature` for `http::Response`.
+///
+/// The `(status)` pseudo-header is signed instead of `(request-target)`, along with every header
+/// of the response.
+impl<T, B> AsHttpSignature<T> for Response<B>
+where
+    T: Read,
+{
+    fn as_http_signature(
+        &self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<HttpSignature<T>, Error> {
+        let mut headers = BTreeMap::new();
+        headers.insert(STATUS.into(), vec![self.status().as_u16().to_string()]);
+
+        let headers = self.headers()
+            .iter()
+            .fold(headers, |mut acc, (name, value)| {
+                acc.entry(name.as_str().into())
+                    .or_insert_with(Vec::new)
+                    .push(String::from_utf8_lossy(value.as_bytes()).into_owned());
+
+                acc
+            });
+
+        HttpSignature::new(key_id, key, algorithm, headers).map_err(Error::from)
+    }
+}
+
+/// An implementation of `VerifyHeader` for `http::Response`, for clients checking the signature
+/// a server put on its response.
+impl<B> VerifyHeader for Response<B> {
+    fn verify_signature_header<G: GetKey>(
+        &self,
+        key_getter: G,
+    ) -> Result<Verification, VerificationError> {
+        let sig_header = self.headers()
+            .get(SIGNATURE)
+            .ok_or(VerificationError::HeaderNotPresent)?;
+
+        verify_response_header(self, from_utf8(sig_header.as_bytes())?, key_getter)
+    }
+
+    fn verify_authorization_header<G: GetKey>(
+        &self,
+        key_getter: G,
+    ) -> Result<Verification, VerificationError> {
+        let auth_header = self.headers()
+            .get(AUTHORIZATION)
+            .ok_or(VerificationError::HeaderNotPresent)?;
+
+        verify_response_header(self, from_utf8(auth_header.as_bytes())?, key_getter)
+    }
+}
+
+fn verify_response_header<B, G>(
+    res: &Response<B>,
+    header: &str,
+    key_getter: G,
+) -> Result<Verification, VerificationError>
+where
+    G: GetKey,
+{
+    let header_pairs = res.headers().header_pairs()?;
+
+    SignedHeader::new(header)?.verify_response(&header_pairs, res.status().as_u16(), key_getter)
+}
+
+/// Header values that aren't valid UTF-8 fail with `VerificationError::Utf8`.
+impl HeaderSource for HeaderMap {
+    fn header_pairs(&self) -> Result<Vec<(&str, &str)>, VerificationError> {
+        let mut pairs = Vec::new();
+        for (name, value) in self.iter() {
+            pairs.push((name.as_str(), from_utf8(value.as_bytes())?));
+        }
+
+        Ok(pairs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+    use std::fs::File;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use http::{Request, Response, StatusCode};
+    use http::header::HeaderValue;
+
+    use create::SigningString;
+    use error::{CreationError, Error, VerificationError};
+    use key::SigningKey;
+    use ShaSize;
+    use SignatureAlgorithm;
+    use REQUEST_TARGET;
+    use prelude::*;
+
+    const KEY_ID: &'static str = "rsa-key-1";
+    const ALGORITHM: SignatureAlgorithm = SignatureAlgorithm::RSA(ShaSize::TwoFiftySix);
+    const PRIVATE_KEY_PATH: &'static str = "tests/assets/private.der";
+    const PUBLIC_KEY_PATH: &'static str = "tests/assets/public.der";
+
+    struct FileKeyGetter;
+
+    impl GetKey for FileKeyGetter {
+        type Key = File;
+        type Error = VerificationError;
+
+        fn get_key(self, _: &str) -> Result<Self::Key, Self::Error> {
+            File::open(PUBLIC_KEY_PATH).map_err(|_| VerificationError::GetKey)
+        }
+    }
+
+    #[test]
+    fn min_test() {
+        let req = Request::post("http://example.org/foo").body(()).unwrap();
+
+        test_request(req, "(request-target): post /foo");
+    }
+
+    #[test]
+    fn full_test() {
+        let req = Request::post("http://example.org/foo?bar=baz")
+            .header("host", "example.org")
+            .header("content-type", "application/json")
+            .header("date", "Tue, 07 Jun 2014 20:51:35 GMT")
+            .body(())
+            .unwrap();
+
+        test_request(
+            req,
+            "(request-target): post /foo?bar=baz
+content-type: application/json
+date: Tue, 07 Jun 2014 20:51:35 GMT
+host: example.org",
+        )
+    }
+
+    #[test]
+    fn multi_value_test() {
+        let mut req = Request::get("http://example.org/foo").body(()).unwrap();
+
+        req.headers_mut()
+            .append("cache-control", "max-age=60".parse().unwrap());
+        req.headers_mut()
+            .append("cache-control", "must-revalidate".parse().unwrap());
+
+        test_request(
+            req,
+            "(request-target): get /foo
+cache-control: max-age=60, must-revalidate",
+        )
+    }
+
+    #[test]
+    fn derived_host_test() {
+        let req = Request::get("http://example.org:8080/foo").body(()).unwrap();
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+
+        let http_sig = req.as_http_signature_with_derived_host(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        assert_eq!(
+            http_sig.signing_string(),
+            "(request-target): get /foo
+host: example.org:8080"
+        );
+
+        let req = Request::get("http://example.org/foo")
+            .header("host", "example.com")
+            .body(())
+            .unwrap();
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+
+        let http_sig = req.as_http_signature_with_derived_host(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        assert_eq!(
+            http_sig.signing_string(),
+            "(request-target): get /foo
+host: example.com"
+        );
+    }
+
+    #[test]
+    fn derived_host_includes_port() {
+        let req = Request::get("https://example.org:8443/foo").body(()).unwrap();
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+
+        let http_sig = req.as_http_signature_with_derived_host(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        assert_eq!(
+            http_sig.signing_string(),
+            "(request-target): get /foo
+host: example.org:8443"
+        );
+    }
+
+    #[test]
+    fn absolute_form_test() {
+        let req = Request::get("http://example.org/foo?bar=baz").body(()).unwrap();
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+
+        let http_sig = req.as_http_signature_absolute_form(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        assert_eq!(
+            http_sig.signing_string(),
+            "(request-target): get http://example.org/foo?bar=baz"
+        );
+
+        let req = Request::get("/foo").body(()).unwrap();
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+
+        let http_sig = req.as_http_signature_absolute_form(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        assert_eq!(http_sig.signing_string(), "(request-target): get /foo");
+    }
+
+    #[test]
+    fn sorted_query_test() {
+        let req = Request::get("http://example.org/foo?b=2&a=1").body(()).unwrap();
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+
+        let http_sig = req.as_http_signature_with_sorted_query(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        assert_eq!(http_sig.signing_string(), "(request-target): get /foo?a=1&b=2");
+
+        let req = Request::get("http://example.org/foo").body(()).unwrap();
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+
+        let http_sig = req.as_http_signature_with_sorted_query(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        assert_eq!(http_sig.signing_string(), "(request-target): get /foo");
+    }
+
+    #[test]
+    fn date_is_added_and_signed() {
+        let mut req = Request::get("http://example.org/foo").body(()).unwrap();
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        req.with_date_and_authorization_header(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        let date = req.headers().get("date").unwrap().to_str().unwrap().to_owned();
+        assert!(date.ends_with(" GMT"));
+
+        let auth_header = req.headers().get("authorization").unwrap().to_str().unwrap();
+        assert!(auth_header.contains(r#"headers="(request-target) date""#));
+
+        req.verify_authorization_header(FileKeyGetter).unwrap();
+
+        let mut req = Request::get("http://example.org/foo")
+            .header("Date", "Tue, 07 Jun 2014 20:51:35 GMT")
+            .body(())
+            .unwrap();
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        req.with_date_and_signature_header(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        assert_eq!(req.headers().get_all("date").iter().count(), 1);
+        assert_eq!(req.headers().get("date").unwrap(), "Tue, 07 Jun 2014 20:51:35 GMT");
+    }
+
+    #[test]
+    fn percent_encoded_paths_are_signed_verbatim() {
+        let mut req = Request::get("http://example.org/foo%20bar/%2F?q=a%26b")
+            .header("Date", "Tue, 07 Jun 2014 20:51:35 GMT")
+            .body(())
+            .unwrap();
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        let http_sig = req.as_http_signature(KEY_ID.into(), key, ALGORITHM).unwrap();
+
+        assert_eq!(
+            http_sig.signing_string(),
+            "(request-target): get /foo%20bar/%2F?q=a%26b
+date: Tue, 07 Jun 2014 20:51:35 GMT"
+        );
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        req.with_authorization_header(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        req.verify_authorization_header(FileKeyGetter).unwrap();
+    }
+
+    #[test]
+    fn signed_response_is_verified() {
+        let mut res = Response::builder()
+            .status(StatusCode::CREATED)
+            .header("Content-Type", "application/json")
+            .header("Date", "Tue, 07 Jun 2014 20:51:35 GMT")
+            .body(())
+            .unwrap();
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        let http_sig = res.as_http_signature(KEY_ID.into(), key, ALGORITHM).unwrap();
+
+        assert_eq!(
+            http_sig.signing_string(),
+            "(status): 201
+content-type: application/json
+date: Tue, 07 Jun 2014 20:51:35 GMT"
+        );
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        let sig_header = res.signature_header(KEY_ID.into(), key, ALGORITHM).unwrap();
+        res.headers_mut()
+            .insert("signature", HeaderValue::from_str(&sig_header).unwrap());
+
+        res.verify_signature_header(FileKeyGetter).unwrap();
+
+        *res.status_mut() = StatusCode::OK;
+
+        match res.verify_signature_header(FileKeyGetter) {
+            Err(VerificationError::BadSignature) => (),
+            res => panic!("Expected BadSignature, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn date_is_read_from_clock() {
+        let mut req = Request::get("http://example.org/foo").body(()).unwrap();
+        let clock = UNIX_EPOCH + Duration::from_secs(1402174295);
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        req.with_date_and_signature_header_from(KEY_ID.into(), key, ALGORITHM, &clock)
+            .unwrap();
+
+        assert_eq!(req.headers().get("date").unwrap(), "Sat, 07 Jun 2014 20:51:35 GMT");
+    }
+
+    #[test]
+    fn signed_request_is_returned_by_value() {
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        let req = Request::get("http://example.org/foo")
+            .header("Date", "Tue, 07 Jun 2014 20:51:35 GMT")
+            .body(())
+            .unwrap()
+            .into_signed_with_authorization(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        req.verify_authorization_header(FileKeyGetter).unwrap();
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        let req = Request::get("http://example.org/foo")
+            .header("Date", "Tue, 07 Jun 2014 20:51:35 GMT")
+            .body(())
+            .unwrap()
+            .into_signed_with_signature(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        req.verify_signature_header(FileKeyGetter).unwrap();
+    }
+
+    #[test]
+    fn request_order_test() {
+        let req = Request::get("http://example.org/foo")
+            .header("Host", "example.org")
+            .header("Date", "Tue, 07 Jun 2014 20:51:35 GMT")
+            .header("Accept", "application/json")
+            .body(())
+            .unwrap();
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        let http_sig = req.as_http_signature(KEY_ID.into(), key, ALGORITHM).unwrap();
+
+        assert_eq!(http_sig.signed_headers(), &[REQUEST_TARGET, "accept", "date", "host"]);
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        let http_sig = req.as_http_signature_in_request_order(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        assert_eq!(http_sig.signed_headers(), &[REQUEST_TARGET, "host", "date", "accept"]);
+        assert_eq!(
+            http_sig.signing_string(),
+            concat!(
+                "(request-target): get /foo\n",
+                "host: example.org\n",
+                "date: Tue, 07 Jun 2014 20:51:35 GMT\n",
+                "accept: application/json"
+            )
+        );
+    }
+
+    #[test]
+    fn digest_from_body_test() {
+        let body = r#"{"hello": "world"}"#;
+        let mut req = Request::post("http://example.org/foo").body(body).unwrap();
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        req.with_authorization_header_and_digest(
+            KEY_ID.into(),
+            key,
+            ALGORITHM,
+            ShaSize::TwoFiftySix,
+        ).unwrap();
+
+        assert_eq!(
+            req.headers().get("digest").unwrap(),
+            "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE="
+        );
+        assert_eq!(*req.body(), body);
+
+        req.verify_authorization_header(FileKeyGetter).unwrap();
+    }
+
+    #[test]
+    fn digest_size_is_independent_of_signature_hash() {
+        use verify::SignedHeader;
+
+        let body = r#"{"hello": "world"}"#;
+        let mut req = Request::post("http://example.org/foo").body(body).unwrap();
+        let algorithm = SignatureAlgorithm::RSA(ShaSize::FiveTwelve);
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        req.with_authorization_header_and_digest(
+            KEY_ID.into(),
+            key,
+            algorithm,
+            ShaSize::TwoFiftySix,
+        ).unwrap();
+
+        assert_eq!(
+            req.headers().get("digest").unwrap(),
+            "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE="
+        );
+
+        let auth_header = req.headers().get("authorization").unwrap().to_str().unwrap();
+        assert!(auth_header.contains(r#"algorithm="rsa-sha512""#));
+
+        let header_pairs = req.headers().header_pairs().unwrap();
+
+        SignedHeader::new(auth_header)
+            .unwrap()
+            .verify_with_body(
+                &header_pairs,
+                "POST",
+                "/foo",
+                None,
+                body.as_bytes(),
+                FileKeyGetter,
+            )
+            .unwrap();
+
+        let res = SignedHeader::new(auth_header).unwrap().verify_with_body(
+            &header_pairs,
+            "POST",
+            "/foo",
+            None,
+            b"tampered",
+            FileKeyGetter,
+        );
+
+        match res {
+            Err(VerificationError::DigestMismatch) => (),
+            res => panic!("Expected DigestMismatch, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn signs_and_verifies() {
+        let mut req = Request::post("http://example.org/foo")
+            .header("date", "Tue, 07 Jun 2014 20:51:35 GMT")
+            .body(())
+            .unwrap();
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        req.with_authorization_header(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+        req.verify_authorization_header(FileKeyGetter).unwrap();
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        req.with_signature_header(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+        req.verify_signature_header(FileKeyGetter).unwrap();
+    }
+
+    #[test]
+    fn signs_with_signing_key() {
+        let mut req = Request::post("http://example.org/foo")
+            .header("date", "Tue, 07 Jun 2014 20:51:35 GMT")
+            .body(())
+            .unwrap();
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        let signing_key = SigningKey::new(KEY_ID.into(), key, ALGORITHM).unwrap();
+
+        req.with_authorization_header_using(&signing_key).unwrap();
+        req.verify_authorization_header(FileKeyGetter).unwrap();
+
+        req.with_signature_header_using(&signing_key).unwrap();
+        req.verify_signature_header(FileKeyGetter).unwrap();
+    }
+
+    #[test]
+    fn resigning_covers_mutated_headers() {
+        let mut req = Request::post("http://example.org/foo")
+            .header("date", "Tue, 07 Jun 2014 20:51:35 GMT")
+            .header("x-attempt", "1")
+            .body(())
+            .unwrap();
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        let signing_key = SigningKey::new(KEY_ID.into(), key, ALGORITHM).unwrap();
+
+        req.with_authorization_header_using(&signing_key).unwrap();
+        let stale = req.headers().get("authorization").unwrap().clone();
+
+        req.headers_mut()
+            .insert("x-attempt", HeaderValue::from_static("2"));
+        assert!(req.verify_authorization_header(FileKeyGetter).is_err());
+
+        req.resign_using(&signing_key).unwrap();
+        req.resign_using(&signing_key).unwrap();
+
+        assert_eq!(req.headers().get_all("authorization").iter().count(), 1);
+        assert!(!req.headers().contains_key("signature"));
+        assert_ne!(req.headers().get("authorization").unwrap(), &stale);
+        req.verify_authorization_header(FileKeyGetter).unwrap();
+
+        req.headers_mut().remove("authorization");
+        req.with_signature_header_using(&signing_key).unwrap();
+        req.headers_mut()
+            .insert("x-attempt", HeaderValue::from_static("3"));
+
+        req.resign_using(&signing_key).unwrap();
+
+        assert!(!req.headers().contains_key("authorization"));
+        req.verify_signature_header(FileKeyGetter).unwrap();
+    }
+
+    #[test]
+    fn existing_bearer_tokens_are_not_overwritten() {
+        let mut req = Request::post("http://example.org/foo")
+            .header("date", "Tue, 07 Jun 2014 20:51:35 GMT")
+            .header("authorization", "Bearer abc123")
+            .body(())
+            .unwrap();
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        match req.with_authorization_header(KEY_ID.into(), key, ALGORITHM) {
+            Err(Error::Creation(CreationError::AuthorizationConflict)) => (),
+            res => panic!("Expected AuthorizationConflict, got {:?}", res.map(|_| ())),
+        }
+        assert_eq!(req.headers().get("authorization").unwrap(), "Bearer abc123");
+
+        // The signature can still be sent alongside the token
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        req.with_signature_header(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+        assert_eq!(req.headers().get("authorization").unwrap(), "Bearer abc123");
+
+        // Signature credentials are replaced as before
+        req.headers_mut().remove("authorization");
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        req.with_authorization_header(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        req.with_authorization_header(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+    }
+
+    fn test_request(req: Request<()>, s: &str) {
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+
+        let http_sig = req.as_http_signature(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        let signing_string: SigningString<File> = http_sig.try_into().unwrap();
+
+        assert_eq!(signing_string.signing_string, s);
+    }
+}
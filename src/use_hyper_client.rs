@@ -90,13 +90,44 @@
 
 use std::io::Read;
 use std::collections::BTreeMap;
+use std::time::SystemTime;
 
-use create::HttpSignature;
+use create::{HttpSignature, SignatureParams};
+use digest::Digest;
 use error::Error;
 use prelude::*;
-use super::{SignatureAlgorithm, REQUEST_TARGET};
+use prelude::signature_params_for;
+use verify::is_signature_scheme;
+use super::{absolute_request_target, absolute_uri, check_authorization, http_date, request_target,
+            sorted_query, ShaSize, SignatureAlgorithm, REQUEST_TARGET};
 
 use hyper::Request as HyperRequest;
+use hyper::header::Authorization;
+
+/// An implementation of `AsSignatureParams` for `hyper::Request`.
+impl AsSignatureParams for HyperRequest {
+    fn as_signature_params(
+        &self,
+        key_id: String,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<SignatureParams, Error> {
+        let mut headers = BTreeMap::new();
+        headers.insert(
+            REQUEST_TARGET.into(),
+            vec![request_target(self.method().as_ref(), self.uri().path(), self.uri().query())],
+        );
+
+        let headers = self.headers().iter().fold(headers, |mut acc, header_view| {
+            acc.entry(header_view.name().into())
+                .or_insert_with(Vec::new)
+                .push(header_view.value_string());
+
+            acc
+        });
+
+        SignatureParams::new(key_id, algorithm, headers).map_err(Error::from)
+    }
+}
 
 /// An implementation of `AsHttpSignature` for `hyper::Request`.
 ///
@@ -112,36 +143,77 @@ where
         key: T,
         algorithm: SignatureAlgorithm,
     ) -> Result<HttpSignature<T>, Error> {
-        let mut headers = BTreeMap::new();
-        headers.insert(
-            REQUEST_TARGET.into(),
-            vec![
-                if let Some(query) = self.uri().query() {
-                    format!(
-                        "{} {}?{}",
-                        self.method().as_ref().to_lowercase(),
-                        self.uri().path(),
-                        query
-                    )
-                } else {
-                    format!(
-                        "{} {}",
-                        self.method().as_ref().to_lowercase(),
-                        self.uri().path()
-                    )
-                },
-            ],
-        );
+        Ok(self.as_signature_params(key_id, algorithm)?.with_key(key))
+    }
 
-        let headers = self.headers().iter().fold(headers, |mut acc, header_view| {
-            acc.entry(header_view.name().into())
-                .or_insert_with(Vec::new)
-                .push(header_view.value_string());
+    fn as_http_signature_with_derived_host(
+        &self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<HttpSignature<T>, Error> {
+        let http_sig = self.as_http_signature(key_id, key, algorithm)?;
 
-            acc
-        });
+        // The Host header keeps the port, but never includes userinfo, which an authority may
+        let host = self.uri()
+            .authority()
+            .map(|authority| authority.rsplit('@').next().unwrap_or(""));
+
+        Ok(match host {
+            Some(host) => http_sig.with_header_if_missing("host", host),
+            None => http_sig,
+        })
+    }
+
+    fn as_http_signature_absolute_form(
+        &self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<HttpSignature<T>, Error> {
+        let http_sig = self.as_http_signature(key_id, key, algorithm)?;
+
+        let uri = self.uri();
+
+        match (uri.scheme(), uri.host()) {
+            (Some(scheme), Some(host)) => {
+                let uri = absolute_uri(scheme, host, uri.port(), uri.path(), uri.query());
 
-        HttpSignature::new(key_id, key, algorithm, headers).map_err(Error::from)
+                Ok(http_sig
+                    .with_request_target(absolute_request_target(self.method().as_ref(), &uri)))
+            }
+            _ => Ok(http_sig),
+        }
+    }
+
+    fn as_http_signature_with_sorted_query(
+        &self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<HttpSignature<T>, Error> {
+        let http_sig = self.as_http_signature(key_id, key, algorithm)?;
+
+        Ok(match self.uri().query() {
+            Some(query) => http_sig.with_request_target(request_target(
+                self.method().as_ref(),
+                self.uri().path(),
+                Some(&sorted_query(query)),
+            )),
+            None => http_sig,
+        })
+    }
+
+    fn as_http_signature_in_request_order(
+        &self,
+        key_id: String,
+        key: T,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<HttpSignature<T>, Error> {
+        let mut header_names = vec![REQUEST_TARGET];
+        header_names.extend(self.headers().iter().map(|header| header.name()));
+
+        self.as_http_signature_with_headers(key_id, key, algorithm, &header_names)
     }
 }
 
@@ -163,12 +235,9 @@ where
         key: T,
         algorithm: SignatureAlgorithm,
     ) -> Result<&mut Self, Error> {
-        use hyper::header::Authorization;
-
         let auth_header = self.authorization_header(key_id, key, algorithm)?;
-        self.headers_mut().set(Authorization(auth_header));
 
-        Ok(self)
+        self.set_authorization_header(auth_header)
     }
 
     fn with_signature_header(
@@ -178,22 +247,104 @@ where
         algorithm: SignatureAlgorithm,
     ) -> Result<&mut Self, Error> {
         let sig_header = self.signature_header(key_id, key, algorithm)?;
-        self.headers_mut().set_raw("Signature", sig_header);
+
+        self.set_signature_header(sig_header)
+    }
+}
+
+/// An implementation of `WithSigningKey` for `hyper::Request`
+///
+/// This adds the same headers as `WithHttpSignature`, without reading a key.
+impl WithSigningKey for HyperRequest {
+    fn with_authorization_header_using<S: Signer>(
+        &mut self,
+        key: &S,
+    ) -> Result<&mut Self, Error> {
+        let auth_header = signature_params_for(&*self, key)?.authorization_header_using(key)?;
+
+        self.set_authorization_header(auth_header)
+    }
+
+    fn with_signature_header_using<S: Signer>(&mut self, key: &S) -> Result<&mut Self, Error> {
+        let sig_header = signature_params_for(&*self, key)?.signature_header_using(key)?;
+
+        self.set_signature_header(sig_header)
+    }
+}
+
+/// An implementation of `SetSignatureHeaders` for `hyper::Request`
+impl SetSignatureHeaders for HyperRequest {
+    fn set_authorization_header(&mut self, value: String) -> Result<&mut Self, Error> {
+        // Several Authorization headers are a conflict too, so they're checked as an empty value
+        check_authorization(
+            self.headers()
+                .get_raw("Authorization")
+                .map(|raw| raw.one().unwrap_or(&[])),
+        )?;
+        self.headers_mut().set(Authorization(value));
+
+        Ok(self)
+    }
+
+    fn set_signature_header(&mut self, value: String) -> Result<&mut Self, Error> {
+        self.headers_mut().set_raw("Signature", value);
 
         Ok(self)
     }
 }
 
+impl Resign for HyperRequest {
+    fn has_signature_header(&self) -> bool {
+        self.headers().get_raw("Signature").is_some()
+    }
+
+    fn remove_signature_headers(&mut self) -> &mut Self {
+        let signed_authorization = self.headers()
+            .get::<Authorization<String>>()
+            .map(|auth| is_signature_scheme(auth))
+            .unwrap_or(false);
+
+        if signed_authorization {
+            self.headers_mut().remove::<Authorization<String>>();
+        }
+        self.headers_mut().remove_raw("Signature");
+
+        self
+    }
+}
+
+impl WithDate for HyperRequest {
+    fn with_date_header_if_missing(&mut self, date: SystemTime) -> &mut Self {
+        if self.headers().get_raw("Date").is_none() {
+            self.headers_mut().set_raw("Date", http_date(date));
+        }
+
+        self
+    }
+}
+
+impl WithDigest for HyperRequest {
+    fn with_digest_header(&mut self, body: &[u8], size: ShaSize) -> &mut Self {
+        let digest = Digest::from_body(body, size);
+        self.headers_mut().set_raw("Digest", digest.to_string());
+
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::convert::TryInto;
     use std::fs::File;
+    use std::io::Cursor;
     use std::str::FromStr;
 
     use hyper::{Method, Request};
     use hyper::header::{ContentLength, ContentType, Date, Host, HttpDate};
 
     use create::SigningString;
+    use error::{CreationError, Error};
+    use key::SigningKey;
     use ShaSize;
     use SignatureAlgorithm;
     use prelude::*;
@@ -222,6 +373,22 @@ mod tests {
         test_request(req, "(request-target): post /foo");
     }
 
+    #[test]
+    fn derived_host_includes_port() {
+        let uri = "https://user@example.org:8443/foo".parse().unwrap();
+        let req: Request = Request::new(Method::Get, uri);
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        let http_sig = req.as_http_signature_with_derived_host(KEY_ID.into(), key, ALGORITHM)
+            .unwrap();
+
+        assert_eq!(
+            http_sig.signing_string(),
+            "(request-target): get /foo
+host: example.org:8443"
+        );
+    }
+
     #[test]
     fn full_test() {
         let uri = "http://example.org/foo".parse().unwrap();
@@ -250,6 +417,152 @@ host: example.org",
         )
     }
 
+    #[test]
+    fn digest_test() {
+        let uri = "http://example.org/foo".parse().unwrap();
+        let mut req = Request::new(Method::Post, uri);
+
+        let body = r#"{"hello": "world"}"#;
+        req.with_digest_header(body.as_bytes(), ShaSize::TwoFiftySix);
+        req.set_body(body);
+
+        test_request(
+            req,
+            "(request-target): post /foo
+digest: SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=",
+        )
+    }
+
+    #[test]
+    fn multi_value_test() {
+        let uri = "http://example.org/foo".parse().unwrap();
+        let mut req = Request::new(Method::Post, uri);
+
+        req.headers_mut()
+            .append_raw("X-Forwarded-For", "192.0.2.1");
+        req.headers_mut()
+            .append_raw("X-Forwarded-For", "198.51.100.7");
+
+        test_request(
+            req,
+            "(request-target): post /foo
+x-forwarded-for: 192.0.2.1, 198.51.100.7",
+        )
+    }
+
+    #[test]
+    fn selected_headers_test() {
+        let uri = "http://example.org/foo".parse().unwrap();
+        let mut req = Request::new(Method::Post, uri);
+
+        req.headers_mut().set(Host::new("example.org", None));
+        req.headers_mut().set(ContentType::json());
+        req.headers_mut().set(Date(
+            HttpDate::from_str("Tue, 07 Jun 2014 20:51:35 GMT").unwrap(),
+        ));
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+
+        let http_sig = req.as_http_signature_with_headers(
+            KEY_ID.into(),
+            key,
+            ALGORITHM,
+            &["Host", "(request-target)", "date"],
+        ).unwrap();
+
+        let signing_string: SigningString<File> = http_sig.into();
+
+        assert_eq!(
+            signing_string.signing_string,
+            "host: example.org
+(request-target): post /foo
+date: Tue, 07 Jun 2014 20:51:35 GMT"
+        );
+    }
+
+    #[test]
+    fn missing_selected_headers_test() {
+        let uri = "http://example.org/foo".parse().unwrap();
+        let req = Request::new(Method::Post, uri);
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+
+        let res = req.as_http_signature_with_headers(KEY_ID.into(), key, ALGORITHM, &["date"]);
+
+        match res {
+            Err(Error::Creation(CreationError::MissingHeaders(missing))) => {
+                assert_eq!(missing, "date")
+            }
+            res => panic!("Expected MissingHeaders, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn selective_authorization_header_test() {
+        let uri = "http://example.org/foo".parse().unwrap();
+        let mut req = Request::new(Method::Post, uri);
+
+        req.headers_mut().set(Host::new("example.org", None));
+        req.headers_mut().set(ContentType::json());
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        req.with_authorization_header_selective(
+            KEY_ID.into(),
+            key,
+            ALGORITHM,
+            &["(request-target)", "host"],
+        ).unwrap();
+
+        let auth_header = req.headers()
+            .get_raw("Authorization")
+            .and_then(|raw| raw.one())
+            .unwrap();
+        let auth_header = String::from_utf8_lossy(auth_header);
+
+        assert!(auth_header.contains("headers=\"(request-target) host\""));
+
+        let key = File::open(PRIVATE_KEY_PATH).unwrap();
+        let res = req.with_signature_header_selective(KEY_ID.into(), key, ALGORITHM, &["date"])
+            .map(|_| ());
+
+        match res {
+            Err(Error::Creation(CreationError::MissingHeaders(missing))) => {
+                assert_eq!(missing, "date")
+            }
+            res => panic!("Expected MissingHeaders, got {:?}", res),
+        }
+        assert!(req.headers().get_raw("Signature").is_none());
+    }
+
+    #[test]
+    fn signature_header_is_a_single_raw_line() {
+        let uri = "http://example.org/foo".parse().unwrap();
+        let mut req: Request = Request::new(Method::Post, uri);
+        req.headers_mut()
+            .set_raw("Date", "Tue, 07 Jun 2014 20:51:35 GMT");
+
+        let algorithm = SignatureAlgorithm::HMAC(ShaSize::TwoFiftySix);
+        let key = Cursor::new(b"shared secret".to_vec());
+        req.with_signature_header("hmac-key-1".into(), key, algorithm.clone())
+            .unwrap();
+
+        let key = Cursor::new(b"shared secret".to_vec());
+        let signing_key = SigningKey::new("hmac-key-1".into(), key, algorithm).unwrap();
+        req.resign_using(&signing_key).unwrap();
+
+        // Signing again replaces the header, rather than adding a second value
+        let sig_header = req.headers()
+            .get_raw("Signature")
+            .and_then(|raw| raw.one())
+            .unwrap();
+
+        assert_eq!(
+            sig_header,
+            &b"keyId=\"hmac-key-1\",algorithm=\"hmac-sha256\",headers=\"(request-target) date\",\
+               signature=\"WrLA+Yvk8wH+negCmtRYVE9FKKyzYAUhJuATqgEqF6M=\""[..]
+        );
+    }
+
     fn test_request(req: Request, s: &str) {
         let key = File::open(PRIVATE_KEY_PATH).unwrap();
 
@@ -28,11 +28,14 @@ use hyper::header::Authorization;
 use hyper::server::Request;
 
 use prelude::*;
-use verify::SignedHeader;
+use verify::{SignedHeader, Verification};
 use error::VerificationError;
 
 impl VerifyHeader for Request {
-    fn verify_signature_header<G: GetKey>(&self, key_getter: G) -> Result<(), VerificationError> {
+    fn verify_signature_header<G: GetKey>(
+        &self,
+        key_getter: G,
+    ) -> Result<Verification, VerificationError> {
         let auth_header = self.headers()
             .get_raw("Signature")
             .ok_or(VerificationError::HeaderNotPresent)?
@@ -45,7 +48,7 @@ impl VerifyHeader for Request {
     fn verify_authorization_header<G: GetKey>(
         &self,
         key_getter: G,
-    ) -> Result<(), VerificationError> {
+    ) -> Result<Verification, VerificationError> {
         let &Authorization(ref auth_header) = self.headers()
             .get::<Authorization<String>>()
             .ok_or(VerificationError::HeaderNotPresent)?;
@@ -54,7 +57,11 @@ impl VerifyHeader for Request {
     }
 }
 
-fn verify_header<G>(req: &Request, header: &str, key_getter: G) -> Result<(), VerificationError>
+fn verify_header<G>(
+    req: &Request,
+    header: &str,
+    key_getter: G,
+) -> Result<Verification, VerificationError>
 where
     G: GetKey,
 {
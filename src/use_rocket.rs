@@ -14,7 +14,7 @@
 // along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
 
 //! Available with the `use_rocket` feature. This module defines `VerifyHeader` for
-//! `rocket::Request`.
+//! `rocket::Request`, and the `SignedRequest` request guard.
 //!
 //! This allows easy verification of incomming requests in Rocket, and can be used with Request
 //! guards.
@@ -22,31 +22,145 @@
 //! See
 //! [this example](https://github.com/asonix/http-signatures/blob/master/examples/rocket.rs)
 //! for usage information.
+//!
+//! # The `SignedRequest` guard
+//!
+//! A route taking a `SignedRequest` only runs if the request carries a valid HTTP Signature, in
+//! either the Authorization or the Signature header. An Authorization header with other
+//! credentials, such as a Bearer token, is skipped in favour of the Signature header. Keys are
+//! looked up with a `KeyResolver`, which must be managed by the Rocket instance.
+//!
+//! ```rust,ignore
+//! #[get("/")]
+//! fn index(signed: SignedRequest) -> String {
+//!     format!("Hello, {}", signed.key_id())
+//! }
+//!
+//! rocket::ignite()
+//!     .mount("/", routes![index])
+//!     .manage(KeyResolver::new(|key_id| lookup_public_key(key_id)))
+//!     .launch();
+//! ```
+//!
+//! Request guards can't read the body, which Rocket gives to the data guard, so a signed Digest
+//! header is only checked as a header. To check it against the body, verify the request in a
+//! `FromData` implementation with `http_signatures::SignedHeader::verify_with_body` instead.
 
-use rocket::Request;
+use std::io::Cursor;
+
+use rocket::{Outcome, Request, State};
+use rocket::http::Status;
+use rocket::request::{self, FromRequest};
 
 use prelude::*;
-use verify::SignedHeader;
+use verify::{is_signature_scheme, SignedHeader, Verification};
 use error::VerificationError;
 
 impl<'r> VerifyHeader for Request<'r> {
-    fn verify_signature_header<G: GetKey>(&self, key_getter: G) -> Result<(), VerificationError> {
+    fn verify_signature_header<G: GetKey>(
+        &self,
+        key_getter: G,
+    ) -> Result<Verification, VerificationError> {
         verify_header(self, "Signature", key_getter)
     }
 
     fn verify_authorization_header<G: GetKey>(
         &self,
         key_getter: G,
-    ) -> Result<(), VerificationError> {
+    ) -> Result<Verification, VerificationError> {
         verify_header(self, "Authorization", key_getter)
     }
 }
 
+/// The `KeyResolver` struct maps a `keyId` to the bytes of its public key (or shared secret, for
+/// HMAC). It must be managed by the Rocket instance for `SignedRequest` to work.
+pub struct KeyResolver {
+    resolver: Box<Fn(&str) -> Option<Vec<u8>> + Send + Sync>,
+}
+
+impl KeyResolver {
+    /// Create a `KeyResolver` from a function. Returning `None` rejects the request.
+    pub fn new<F>(resolver: F) -> Self
+    where
+        F: Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
+    {
+        KeyResolver {
+            resolver: Box::new(resolver),
+        }
+    }
+}
+
+impl<'a> GetKey for &'a KeyResolver {
+    type Key = Cursor<Vec<u8>>;
+    type Error = VerificationError;
+
+    fn get_key(self, key_id: &str) -> Result<Self::Key, Self::Error> {
+        (self.resolver)(key_id)
+            .map(Cursor::new)
+            .ok_or(VerificationError::GetKey)
+    }
+}
+
+/// The `SignedRequest` request guard verifies the HTTP Signature of a request.
+///
+/// On success, it holds the details of the signature, including the `keyId` of the key the
+/// request was signed with. Requests that fail verification, including those whose key can't be
+/// resolved, fail with `401 Unauthorized`. If no `KeyResolver` is managed, requests fail with
+/// `500 Internal Server Error`.
+#[derive(Clone, Debug)]
+pub struct SignedRequest {
+    verification: Verification,
+}
+
+impl SignedRequest {
+    /// The `keyId` of the key that signed the request
+    pub fn key_id(&self) -> &str {
+        self.verification.key_id()
+    }
+
+    /// The details of the signature, such as which headers it covers
+    pub fn verification(&self) -> &Verification {
+        &self.verification
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for SignedRequest {
+    type Error = VerificationError;
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let resolver = match request.guard::<State<KeyResolver>>() {
+            Outcome::Success(resolver) => resolver,
+            _ => {
+                return Outcome::Failure((Status::InternalServerError, VerificationError::GetKey))
+            }
+        };
+
+        // Other credentials, such as a Bearer token, may be sent alongside a Signature header
+        let signed_authorization = request
+            .headers()
+            .get_one("Authorization")
+            .map(is_signature_scheme)
+            .unwrap_or(false);
+
+        let header = if signed_authorization {
+            "Authorization"
+        } else {
+            "Signature"
+        };
+
+        match verify_header(request, header, resolver.inner()) {
+            Ok(verification) => Outcome::Success(SignedRequest { verification }),
+            Err(e) => Outcome::Failure((Status::Unauthorized, e)),
+        }
+    }
+}
+
+/// Verify the named header, returning the details of the signature
 fn verify_header<'r, G>(
     req: &Request<'r>,
     header: &str,
     key_getter: G,
-) -> Result<(), VerificationError>
+) -> Result<Verification, VerificationError>
 where
     G: GetKey,
 {
@@ -54,20 +168,15 @@ where
         .get_one(header)
         .ok_or(VerificationError::HeaderNotPresent)?;
 
-    let auth_header = SignedHeader::new(auth_header)?;
-
     let headers: Vec<(String, String)> = req.headers()
         .iter()
         .map(|header| (header.name().into(), header.value().into()))
         .collect();
 
-    let headers_borrowed: Vec<(&str, &str)> = headers
-        .iter()
-        .map(|&(ref key, ref val)| (key.as_ref(), val.as_ref()))
-        .collect();
+    let header_pairs = headers.header_pairs()?;
 
-    auth_header.verify(
-        headers_borrowed.as_ref(),
+    SignedHeader::new(auth_header)?.verify(
+        &header_pairs,
         req.method().as_str(),
         req.uri().path(),
         req.uri().query(),
@@ -0,0 +1,353 @@
+// This file is part of HTTP Signatures
+
+// HTTP Signatures is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// HTTP Signatures is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
+
+//! Available with the `use_tower` feature. This module defines `SignatureLayer`, which signs
+//! every `http::Request` passing through a tower `Service`.
+//!
+//! The layer holds a parsed `SigningKey`, so signing a request never reads or parses the key.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! # extern crate http_signatures;
+//! use http_signatures::{SigningKey, SignatureAlgorithm, ShaSize};
+//! use http_signatures::use_tower::{HeaderStyle, SignatureLayer};
+//!
+//! let key = SigningKey::new("rsa-key-1".into(), File::open("private.der")?, alg)?;
+//!
+//! let client = SignatureLayer::new(key)
+//!     .with_signed_headers(&["(request-target)", "host", "date"])
+//!     .with_header_style(HeaderStyle::Signature)
+//!     .wrap(http_client);
+//! ```
+//!
+//! A request can sign a different set of headers than the layer by carrying a `SignedHeaderNames`
+//! extension, for example when one endpoint requires `digest` to be signed:
+//!
+//! ```rust,ignore
+//! req.extensions_mut()
+//!     .insert(SignedHeaderNames::new(&["(request-target)", "date", "digest"]));
+//! ```
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::sync::Arc;
+
+use futures::{Future, Poll};
+use futures::future::{self, Either, FutureResult, MapErr};
+use http::Request;
+use tower_service::Service;
+
+use error::Error;
+use key::SigningKey;
+use prelude::*;
+
+/// Which header a `SignatureLayer` adds the signature to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeaderStyle {
+    /// `Authorization: Signature keyId=...`
+    Authorization,
+    /// `Signature: keyId=...`
+    Signature,
+}
+
+/// A request extension that chooses the headers signed for that request, instead of those chosen
+/// with `SignatureLayer::with_signed_headers`. The layer's key and header style are still used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedHeaderNames(Vec<String>);
+
+impl SignedHeaderNames {
+    /// Sign the named headers, in the order they are given
+    pub fn new(header_names: &[&str]) -> Self {
+        SignedHeaderNames(header_names.iter().map(|name| name.to_string()).collect())
+    }
+}
+
+/// The `SignatureLayer` struct holds everything needed to sign outgoing requests.
+///
+/// By default, every header present on a request is signed, and the signature is added as an
+/// Authorization header.
+#[derive(Clone, Debug)]
+pub struct SignatureLayer {
+    key: Arc<SigningKey>,
+    headers: Option<Vec<String>>,
+    style: HeaderStyle,
+}
+
+impl SignatureLayer {
+    pub fn new(key: SigningKey) -> Self {
+        SignatureLayer {
+            key: Arc::new(key),
+            headers: None,
+            style: HeaderStyle::Authorization,
+        }
+    }
+
+    /// Only sign the named headers, in the order they are given. Requests missing one of these
+    /// headers fail rather than being sent unsigned.
+    pub fn with_signed_headers(mut self, header_names: &[&str]) -> Self {
+        self.headers = Some(header_names.iter().map(|name| name.to_string()).collect());
+        self
+    }
+
+    /// Choose which header the signature is added to
+    pub fn with_header_style(mut self, style: HeaderStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Wrap a service, so requests are signed before they are passed to it
+    pub fn wrap<S>(&self, inner: S) -> SignatureService<S> {
+        SignatureService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+
+    fn sign<B>(&self, req: &mut Request<B>) -> Result<(), Error> {
+        let key_id = self.key.key_id().to_owned();
+        let algorithm = self.key.algorithm().clone();
+
+        let header_names = req.extensions()
+            .get::<SignedHeaderNames>()
+            .map(|header_names| header_names.0.clone())
+            .or_else(|| self.headers.clone());
+
+        let params = match header_names {
+            Some(ref header_names) => {
+                let header_names: Vec<&str> =
+                    header_names.iter().map(|name| name.as_str()).collect();
+
+                req.as_signature_params_with_headers(key_id, algorithm, &header_names)?
+            }
+            None => req.as_signature_params(key_id, algorithm)?,
+        };
+
+        match self.style {
+            HeaderStyle::Authorization => {
+                req.set_authorization_header(params.authorization_header_using(&*self.key)?)?;
+            }
+            HeaderStyle::Signature => {
+                req.set_signature_header(params.signature_header_using(&*self.key)?)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A tower `Service` that signs requests before passing them to the wrapped service
+#[derive(Clone, Debug)]
+pub struct SignatureService<S> {
+    inner: S,
+    layer: SignatureLayer,
+}
+
+impl<S, B> Service for SignatureService<S>
+where
+    S: Service<Request = Request<B>>,
+{
+    type Request = Request<B>;
+    type Response = S::Response;
+    type Error = SignatureError<S::Error>;
+    type Future = Either<
+        FutureResult<S::Response, SignatureError<S::Error>>,
+        MapErr<S::Future, fn(S::Error) -> SignatureError<S::Error>>,
+    >;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready().map_err(SignatureError::Inner)
+    }
+
+    fn call(&mut self, mut req: Self::Request) -> Self::Future {
+        match self.layer.sign(&mut req) {
+            Ok(()) => Either::B(
+                self.inner
+                    .call(req)
+                    .map_err(SignatureError::Inner as fn(S::Error) -> SignatureError<S::Error>),
+            ),
+            Err(e) => Either::A(future::err(SignatureError::Signing(e))),
+        }
+    }
+}
+
+/// When a `SignatureService` request fails
+#[derive(Debug)]
+pub enum SignatureError<E> {
+    /// The request could not be signed
+    Signing(Error),
+    /// The wrapped service failed
+    Inner(E),
+}
+
+impl<E> fmt::Display for SignatureError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SignatureError::Signing(ref se) => write!(f, "{}", se),
+            SignatureError::Inner(ref ie) => write!(f, "{}", ie),
+        }
+    }
+}
+
+impl<E> StdError for SignatureError<E>
+where
+    E: StdError + 'static,
+{
+    fn description(&self) -> &str {
+        match *self {
+            SignatureError::Signing(ref se) => se.description(),
+            SignatureError::Inner(ref ie) => ie.description(),
+        }
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        match *self {
+            SignatureError::Signing(ref se) => Some(se),
+            SignatureError::Inner(ref ie) => Some(ie),
+        }
+    }
+
+    fn source(&self) -> Option<&(StdError + 'static)> {
+        match *self {
+            SignatureError::Signing(ref se) => Some(se),
+            SignatureError::Inner(ref ie) => Some(ie),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use futures::Future;
+    use futures::future::{self, FutureResult};
+    use futures::Poll;
+    use http::Request;
+    use tower_service::Service;
+
+    use key::SigningKey;
+    use prelude::*;
+    use super::{HeaderStyle, SignatureError, SignatureLayer, SignedHeaderNames};
+    use error::VerificationError;
+    use ShaSize;
+    use SignatureAlgorithm;
+
+    struct Echo;
+
+    impl Service for Echo {
+        type Request = Request<()>;
+        type Response = Request<()>;
+        type Error = ();
+        type Future = FutureResult<Request<()>, ()>;
+
+        fn poll_ready(&mut self) -> Poll<(), ()> {
+            Ok(().into())
+        }
+
+        fn call(&mut self, req: Request<()>) -> Self::Future {
+            future::ok(req)
+        }
+    }
+
+    struct KeyGetter;
+
+    impl GetKey for KeyGetter {
+        type Key = Cursor<Vec<u8>>;
+        type Error = VerificationError;
+
+        fn get_key(self, _: &str) -> Result<Self::Key, Self::Error> {
+            Ok(Cursor::new(vec![1u8, 2, 3]))
+        }
+    }
+
+    fn layer() -> SignatureLayer {
+        let key = SigningKey::new(
+            "hmac-key-1".into(),
+            Cursor::new(vec![1u8, 2, 3]),
+            SignatureAlgorithm::HMAC(ShaSize::TwoFiftySix),
+        ).unwrap();
+
+        SignatureLayer::new(key)
+    }
+
+    fn request() -> Request<()> {
+        Request::get("http://example.org/foo")
+            .header("date", "Tue, 07 Jun 2014 20:51:35 GMT")
+            .body(())
+            .unwrap()
+    }
+
+    #[test]
+    fn signs_authorization_header() {
+        let req = layer().wrap(Echo).call(request()).wait().unwrap();
+
+        req.verify_authorization_header(KeyGetter).unwrap();
+    }
+
+    #[test]
+    fn signs_signature_header() {
+        let req = layer()
+            .with_header_style(HeaderStyle::Signature)
+            .with_signed_headers(&["(request-target)", "date"])
+            .wrap(Echo)
+            .call(request())
+            .wait()
+            .unwrap();
+
+        assert!(req.headers().get("authorization").is_none());
+
+        let sig_header = req.headers().get("signature").unwrap().to_str().unwrap();
+        assert!(sig_header.contains("headers=\"(request-target) date\""));
+
+        req.verify_signature_header(KeyGetter).unwrap();
+    }
+
+    #[test]
+    fn missing_signed_header_fails() {
+        let res = layer()
+            .with_signed_headers(&["host"])
+            .wrap(Echo)
+            .call(request())
+            .wait();
+
+        match res {
+            Err(SignatureError::Signing(_)) => (),
+            res => panic!("Expected a signing error, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn signed_headers_can_be_chosen_per_request() {
+        let layer = layer().with_signed_headers(&["(request-target)"]);
+
+        let mut req = request();
+        req.extensions_mut()
+            .insert(SignedHeaderNames::new(&["(request-target)", "date"]));
+
+        let req = layer.wrap(Echo).call(req).wait().unwrap();
+
+        let auth_header = req.headers().get("authorization").unwrap().to_str().unwrap();
+        assert!(auth_header.contains("headers=\"(request-target) date\""));
+        req.verify_authorization_header(KeyGetter).unwrap();
+
+        let req = layer.wrap(Echo).call(request()).wait().unwrap();
+
+        let auth_header = req.headers().get("authorization").unwrap().to_str().unwrap();
+        assert!(auth_header.contains("headers=\"(request-target)\""));
+    }
+}
@@ -0,0 +1,306 @@
+// This file is part of HTTP Signatures
+
+// HTTP Signatures is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// HTTP Signatures is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
+
+//! Available with the `use_warp` feature. This module defines warp filters that verify the HTTP
+//! Signature of a request.
+//!
+//! `verify_signature` checks the Authorization or Signature header, and extracts the
+//! `Verification` describing the signature, including the `keyId` of the key that signed the
+//! request. An Authorization header with other credentials, such as a
+//! Bearer token, is skipped in favour of the Signature header. `verify_signature_with_body` also
+//! reads the body, so a signed Digest header is checked against it, and passes the body on.
+//! Requests that fail verification are rejected, and `recover` turns those rejections into
+//! `401 Unauthorized` responses.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! # extern crate http_signatures;
+//! # extern crate warp;
+//! use http_signatures::Verification;
+//! use http_signatures::use_warp::{recover, verify_signature};
+//! use warp::Filter;
+//!
+//! let hello = warp::path("hello")
+//!     .and(verify_signature(key_getter))
+//!     .map(|verification: Verification| format!("Hello, {}", verification.key_id()))
+//!     .recover(recover);
+//!
+//! warp::serve(hello).run(([127, 0, 0, 1], 3030));
+//! ```
+
+use std::str::from_utf8;
+
+use bytes::Buf;
+use warp::{self, Filter, Rejection};
+use warp::body::FullBody;
+use warp::http::{HeaderMap, Method, StatusCode};
+use warp::path::FullPath;
+
+use error::VerificationError;
+use prelude::*;
+use use_http::signature_header;
+use verify::{SignedHeader, Verification};
+
+/// A filter that verifies the HTTP Signature of a request, and extracts the details of the
+/// signature, including the `keyId` of the key that signed it.
+///
+/// The body is not read, so a signed Digest header is only checked as a header. Use
+/// `verify_signature_with_body` when requests carry a Digest header.
+pub fn verify_signature<G>(
+    key_getter: G,
+) -> impl Filter<Extract = (Verification,), Error = Rejection> + Clone
+where
+    G: GetKey + Clone + Send + Sync + 'static,
+{
+    warp::method()
+        .and(warp::path::full())
+        .and(
+            warp::query::raw()
+                .map(Some)
+                .or(warp::any().map(|| None))
+                .unify(),
+        )
+        .and(warp::header::headers_cloned())
+        .and_then(
+            move |method: Method, path: FullPath, query: Option<String>, headers: HeaderMap| {
+                verify_request(
+                    &method,
+                    path.as_str(),
+                    query.as_ref().map(|query| query.as_str()),
+                    &headers,
+                    None,
+                    key_getter.clone(),
+                ).map_err(warp::reject::custom)
+            },
+        )
+}
+
+/// A filter that verifies the HTTP Signature of a request, including its Digest header if it is
+/// signed, and extracts the details of the signature along with the body.
+///
+/// The whole body is read into memory, so this should be combined with
+/// `warp::body::content_length_limit`.
+pub fn verify_signature_with_body<G>(
+    key_getter: G,
+) -> impl Filter<Extract = (Verification, Vec<u8>), Error = Rejection> + Clone
+where
+    G: GetKey + Clone + Send + Sync + 'static,
+{
+    warp::method()
+        .and(warp::path::full())
+        .and(
+            warp::query::raw()
+                .map(Some)
+                .or(warp::any().map(|| None))
+                .unify(),
+        )
+        .and(warp::header::headers_cloned())
+        .and(warp::body::concat())
+        .and_then(
+            move |method: Method,
+                  path: FullPath,
+                  query: Option<String>,
+                  headers: HeaderMap,
+                  body: FullBody| {
+                let body = body.bytes().to_vec();
+
+                verify_request(
+                    &method,
+                    path.as_str(),
+                    query.as_ref().map(|query| query.as_str()),
+                    &headers,
+                    Some(&body),
+                    key_getter.clone(),
+                ).map(|verification| (verification, body))
+                    .map_err(warp::reject::custom)
+            },
+        )
+        .untuple_one()
+}
+
+/// Turn rejections from `verify_signature` and `verify_signature_with_body` into
+/// `401 Unauthorized` responses, for use with `Filter::recover`. Other rejections are passed on.
+pub fn recover(err: Rejection) -> Result<StatusCode, Rejection> {
+    if err.find_cause::<VerificationError>().is_some() {
+        Ok(StatusCode::UNAUTHORIZED)
+    } else {
+        Err(err)
+    }
+}
+
+fn verify_request<G>(
+    method: &Method,
+    path: &str,
+    query: Option<&str>,
+    headers: &HeaderMap,
+    body: Option<&[u8]>,
+    key_getter: G,
+) -> Result<Verification, VerificationError>
+where
+    G: GetKey,
+{
+    let header = signature_header(headers).ok_or(VerificationError::HeaderNotPresent)?;
+
+    let signed_header = SignedHeader::new(from_utf8(header.as_bytes())?)?;
+    let header_pairs = headers.header_pairs()?;
+
+    match body {
+        Some(body) => signed_header.verify_with_body(
+            &header_pairs,
+            method.as_str(),
+            path,
+            query,
+            body,
+            key_getter,
+        ),
+        None => signed_header.verify(&header_pairs, method.as_str(), path, query, key_getter),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::io::Cursor;
+
+    use warp::{self, Filter};
+    use warp::http::StatusCode;
+
+    use create::HttpSignature;
+    use digest::Digest;
+    use error::VerificationError;
+    use prelude::*;
+    use super::{recover, verify_signature, verify_signature_with_body};
+    use ShaSize;
+    use SignatureAlgorithm;
+    use REQUEST_TARGET;
+
+    const KEY: &[u8] = b"shared secret";
+    const DATE: &str = "Tue, 07 Jun 2014 20:51:35 GMT";
+    const BODY: &str = r#"{"hello": "world"}"#;
+
+    #[derive(Clone)]
+    struct KeyGetter;
+
+    impl GetKey for KeyGetter {
+        type Key = Cursor<Vec<u8>>;
+        type Error = VerificationError;
+
+        fn get_key(self, key_id: &str) -> Result<Self::Key, Self::Error> {
+            if key_id == "hmac-key-1" {
+                Ok(Cursor::new(KEY.to_vec()))
+            } else {
+                Err(VerificationError::GetKey)
+            }
+        }
+    }
+
+    fn signature(method: &str, path: &str, digest: Option<&str>) -> HttpSignature<&'static [u8]> {
+        let mut headers = BTreeMap::new();
+        headers.insert(REQUEST_TARGET.into(), vec![format!("{} {}", method, path)]);
+        headers.insert("date".into(), vec![DATE.into()]);
+
+        if let Some(digest) = digest {
+            headers.insert("digest".into(), vec![digest.into()]);
+        }
+
+        HttpSignature::new(
+            "hmac-key-1".into(),
+            KEY,
+            SignatureAlgorithm::HMAC(ShaSize::TwoFiftySix),
+            headers,
+        ).unwrap()
+    }
+
+    fn auth_header(method: &str, path: &str, digest: Option<&str>) -> String {
+        signature(method, path, digest)
+            .authorization_header()
+            .unwrap()
+    }
+
+    #[test]
+    fn accepts_signed_request() {
+        let verification = warp::test::request()
+            .path("/foo")
+            .header("date", DATE)
+            .header("authorization", auth_header("get", "/foo", None))
+            .filter(&verify_signature(KeyGetter))
+            .unwrap();
+
+        assert_eq!(verification.key_id(), "hmac-key-1");
+        assert!(verification.covers("date"));
+    }
+
+    #[test]
+    fn bearer_token_does_not_hide_signature_header() {
+        let sig_header = signature("get", "/foo", None).signature_header().unwrap();
+
+        let verification = warp::test::request()
+            .path("/foo")
+            .header("date", DATE)
+            .header("authorization", "Bearer abc")
+            .header("signature", sig_header.as_str())
+            .filter(&verify_signature(KeyGetter))
+            .unwrap();
+
+        assert_eq!(verification.key_id(), "hmac-key-1");
+    }
+
+    #[test]
+    fn rejects_tampered_request() {
+        let filter = verify_signature(KeyGetter)
+            .map(|_| StatusCode::OK)
+            .recover(recover);
+
+        let res = warp::test::request()
+            .path("/bar")
+            .header("date", DATE)
+            .header("authorization", auth_header("get", "/foo", None))
+            .reply(&filter);
+
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn checks_signed_digest_against_body() {
+        let digest = Digest::from_body(BODY.as_bytes(), ShaSize::TwoFiftySix).to_string();
+        let header = auth_header("post", "/foo", Some(&digest));
+
+        let (verification, body) = warp::test::request()
+            .method("POST")
+            .path("/foo")
+            .header("date", DATE)
+            .header("digest", digest.as_str())
+            .header("authorization", header.as_str())
+            .body(BODY)
+            .filter(&verify_signature_with_body(KeyGetter))
+            .unwrap();
+
+        assert_eq!(verification.key_id(), "hmac-key-1");
+        assert!(verification.digest_checked());
+        assert_eq!(body, BODY.as_bytes());
+
+        let res = warp::test::request()
+            .method("POST")
+            .path("/foo")
+            .header("date", DATE)
+            .header("digest", digest.as_str())
+            .header("authorization", header.as_str())
+            .body("tampered")
+            .filter(&verify_signature_with_body(KeyGetter));
+
+        assert!(res.is_err());
+    }
+}
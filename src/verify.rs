@@ -16,21 +16,42 @@
 use std::io::Read;
 use std::convert::{TryFrom, TryInto};
 use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use ring::{digest, hmac, signature};
 use ring::error::Unspecified;
 use base64::decode;
 use untrusted::Input;
 
+use digest::Digest;
+use key::{is_public_key, is_rsa_public_key, ED25519_PUBLIC_KEY_LEN};
 use prelude::*;
-use super::{ShaSize, SignatureAlgorithm, REQUEST_TARGET};
-use error::{DecodeError, VerificationError};
+use super::{request_target, unfold_header_value, ShaSize, SignatureAlgorithm, CREATED, EXPIRES,
+            REQUEST_TARGET, STATUS};
+use error::{DecodeError, Error, VerificationError};
+use trace;
 
+const SCHEME: &str = "Signature";
 const KEY_ID: &str = "keyId";
 const HEADERS: &str = "headers";
 const ALGORITHM: &str = "algorithm";
 const DATE: &str = "date";
+const DIGEST: &str = "digest";
 const SIGNATURE: &str = "signature";
+const CREATED_PARAM: &str = "created";
+const EXPIRES_PARAM: &str = "expires";
+const KNOWN_PARAMS: [&str; 6] = [
+    KEY_ID,
+    ALGORITHM,
+    HEADERS,
+    CREATED_PARAM,
+    EXPIRES_PARAM,
+    SIGNATURE,
+];
+
+/// The clock skew, in seconds, allowed when checking the `created` and `expires` parameters,
+/// unless changed with `SignedHeader::with_max_skew`
+pub const DEFAULT_MAX_SKEW_SECS: u64 = 30;
 
 /// The `SignedHeader` struct is the direct reasult of reading in the Authorization or Signature
 /// header from a given request.
@@ -39,20 +60,232 @@ const SIGNATURE: &str = "signature";
 /// string, the algorithm used to create the signature, and the signature itself.
 ///
 /// It also contains the `key_id`, which will be handled by a type implementing `GetKey`.
+///
+/// Verification never compares secret-dependent bytes with `==`. HMAC signatures and Digest
+/// headers are compared in constant time, and RSA and Ed25519 signatures are checked by Ring.
 #[derive(Debug)]
 pub struct SignedHeader<'a> {
     key_id: &'a str,
     header_keys: Vec<&'a str>,
     algorithm: SignatureAlgorithm,
+    created: Option<u64>,
+    expires: Option<u64>,
     signature: Vec<u8>,
+    max_skew: Duration,
+    max_age: Option<Duration>,
+    check_timestamps: bool,
+    now: Option<SystemTime>,
+    required_headers: Vec<String>,
 }
 
 impl<'a> SignedHeader<'a> {
     /// Try to create an `SignedHeader` from a given String.
+    ///
+    /// This accepts both the value of a Signature header and the value of an Authorization header
+    /// using the `Signature` scheme. The `algorithm` parameter is required, as it is in the
+    /// specification this crate implements.
     pub fn new(s: &'a str) -> Result<Self, DecodeError> {
         s.try_into()
     }
 
+    /// Try to create a `SignedHeader`, failing with `DecodeError::UnknownParameter` if the header
+    /// has a parameter other than `keyId`, `algorithm`, `headers`, `created`, `expires` and
+    /// `signature`.
+    ///
+    /// `new` ignores unknown parameters, as the specification asks. Parsing strictly catches
+    /// typos such as `header=`, which would otherwise silently fall back to only signing `date`.
+    pub fn new_strict(s: &'a str) -> Result<Self, DecodeError> {
+        let signed_header = SignedHeader::new(s)?;
+
+        for (_, key, _) in params(strip_scheme(s.trim()))? {
+            if !KNOWN_PARAMS.contains(&key) {
+                return Err(DecodeError::UnknownParameter(key.to_owned()));
+            }
+        }
+
+        Ok(signed_header)
+    }
+
+    /// Parse every signature in the values of a request's Signature headers.
+    ///
+    /// The draft this crate implements puts one signature in each Signature header, so a request
+    /// with several signatures repeats the header. Repeated headers may also be joined into one
+    /// value with commas, in which case a new signature starts where a parameter, such as
+    /// `keyId`, appears again. RFC 9421 instead labels each signature in a dictionary, which the
+    /// `rfc9421` module handles.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use http_signatures::SignedHeader;
+    ///
+    /// let joined = concat!(
+    ///     r#"keyId="a",algorithm="hmac-sha256",signature="AQID", "#,
+    ///     r#"keyId="b",algorithm="ed25519",signature="BAUG""#
+    /// );
+    ///
+    /// let signatures = SignedHeader::parse_all(vec![joined]).unwrap();
+    ///
+    /// assert_eq!(signatures.len(), 2);
+    /// assert_eq!(signatures[1].key_id(), "b");
+    /// ```
+    pub fn parse_all<I>(values: I) -> Result<Vec<Self>, DecodeError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut signatures = Vec::new();
+
+        for value in values {
+            for signature in split_signatures(strip_scheme(value.trim()))? {
+                signatures.push(SignedHeader::new(signature)?);
+            }
+        }
+
+        Ok(signatures)
+    }
+
+    /// The `keyId` parameter of the header
+    pub fn key_id(&self) -> &str {
+        self.key_id
+    }
+
+    /// The names of the signed headers, in signing order
+    pub fn header_keys(&self) -> &[&'a str] {
+        &self.header_keys
+    }
+
+    /// The algorithm used to create the signature
+    pub fn algorithm(&self) -> &SignatureAlgorithm {
+        &self.algorithm
+    }
+
+    /// The `created` parameter of the header, if present
+    pub fn created(&self) -> Option<u64> {
+        self.created
+    }
+
+    /// The `expires` parameter of the header, if present
+    pub fn expires(&self) -> Option<u64> {
+        self.expires
+    }
+
+    /// The decoded signature
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Set how far the verifier's clock may drift from the signer's.
+    ///
+    /// During verification, a `created` timestamp up to `max_skew` in the future is accepted, as is
+    /// an `expires` timestamp up to `max_skew` in the past. This defaults to
+    /// `DEFAULT_MAX_SKEW_SECS` seconds.
+    pub fn with_max_skew(mut self, max_skew: Duration) -> Self {
+        self.max_skew = max_skew;
+        self
+    }
+
+    /// Reject signatures created more than `max_age` ago.
+    ///
+    /// A signature with `created` but no `expires` is otherwise valid forever, so this bounds how
+    /// long it can be replayed. It fails with `VerificationError::Expired`, and the clock skew set
+    /// by `with_max_skew` is allowed on top of `max_age`. An `expires` parameter doesn't extend
+    /// the window, and signatures without `created` aren't affected.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Don't check the `created` and `expires` parameters against the current time.
+    ///
+    /// By default, expired signatures fail with `VerificationError::Expired`, and signatures
+    /// created in the future with `VerificationError::NotYetValid`. This is only meant for tests
+    /// that verify recorded requests, since it lets old signatures be replayed.
+    pub fn without_timestamp_checks(mut self) -> Self {
+        self.check_timestamps = false;
+        self
+    }
+
+    /// Check the `created` and `expires` parameters against the time read from `clock`, rather
+    /// than the system clock.
+    ///
+    /// The time is read once, when this is called. Passing a fixed `SystemTime` lets tests verify
+    /// recorded requests at the time they were signed, while still checking their timestamps.
+    pub fn with_clock<C: Clock>(mut self, clock: &C) -> Self {
+        self.now = Some(clock.now());
+        self
+    }
+
+    /// Require the named headers to be signed.
+    ///
+    /// Verification fails with `VerificationError::MissingSignedHeader` if one of these is not in
+    /// the `headers` parameter, even if the signature is otherwise valid. Otherwise a signer could
+    /// sign only trivial headers, and leave the ones the application relies on, such as `date`,
+    /// `(request-target)` or `digest`, unsigned. Names are matched case-insensitively.
+    pub fn require_headers(mut self, header_names: &[&str]) -> Self {
+        self.required_headers
+            .extend(header_names.iter().map(|name| name.to_lowercase()));
+        self
+    }
+
+    /// Whether the `digest` header is signed, in which case the body must be checked against it
+    pub fn signs_digest(&self) -> bool {
+        self.header_keys
+            .iter()
+            .any(|header| header.eq_ignore_ascii_case(DIGEST))
+    }
+
+    /// Check that every required header is signed
+    fn check_required_headers(&self) -> Result<(), VerificationError> {
+        for required in &self.required_headers {
+            let signed = self.header_keys
+                .iter()
+                .any(|header| header.to_lowercase() == *required);
+
+            if !signed {
+                return Err(VerificationError::MissingSignedHeader(required.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the `created` and `expires` parameters against the current time, or the time read
+    /// from the clock given to `with_clock`
+    fn check_timestamps(&self) -> Result<(), VerificationError> {
+        if !self.check_timestamps {
+            return Ok(());
+        }
+
+        let now = self.now
+            .unwrap_or_else(SystemTime::now)
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_secs())
+            .unwrap_or(0);
+        let max_skew = self.max_skew.as_secs();
+
+        if let Some(created) = self.created {
+            if created > now.saturating_add(max_skew) {
+                return Err(VerificationError::NotYetValid);
+            }
+        }
+
+        if let Some(expires) = self.expires {
+            if expires.saturating_add(max_skew) < now {
+                return Err(VerificationError::Expired);
+            }
+        }
+
+        if let (Some(created), Some(max_age)) = (self.created, self.max_age) {
+            if created
+                .saturating_add(max_age.as_secs())
+                .saturating_add(max_skew) < now
+            {
+                return Err(VerificationError::Expired);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Try to verify the current `SignedHeader`.
     pub fn verify<G>(
         self,
@@ -61,99 +294,767 @@ impl<'a> SignedHeader<'a> {
         path: &str,
         query: Option<&str>,
         key_getter: G,
-    ) -> Result<(), VerificationError>
+    ) -> Result<Verification, VerificationError>
     where
         G: GetKey,
     {
         let vah = CheckSignedHeader {
             auth_header: self,
             headers: headers,
-            method: method,
-            path: path,
-            query: query,
+            pseudo_header: (REQUEST_TARGET, request_target(method, path, query)),
+            body: None,
         };
 
         vah.verify(key_getter)
     }
+
+    /// Try to verify the current `SignedHeader`, and the request body.
+    ///
+    /// If the `digest` header is signed, the Digest header is checked against `body`, and this
+    /// fails with `VerificationError::DigestMismatch` if they differ.
+    pub fn verify_with_body<G>(
+        self,
+        headers: &[(&str, &str)],
+        method: &str,
+        path: &str,
+        query: Option<&str>,
+        body: &[u8],
+        key_getter: G,
+    ) -> Result<Verification, VerificationError>
+    where
+        G: GetKey,
+    {
+        let vah = CheckSignedHeader {
+            auth_header: self,
+            headers: headers,
+            pseudo_header: (REQUEST_TARGET, request_target(method, path, query)),
+            body: Some(body),
+        };
+
+        vah.verify(key_getter)
+    }
+
+    /// Verify the current `SignedHeader`, then read the whole body from `body` and verify it, and
+    /// return the body along with the details of the signature.
+    ///
+    /// This suits handlers that process the body after verifying it, when the body can only be
+    /// read once. Like `verify_with_lazy_body`, the body is only read once the signature has been
+    /// verified, so unauthenticated clients can't make the server buffer their bodies. Signed
+    /// clients can still send large bodies, so `body` should be limited, for example with
+    /// `Read::take`. The body is returned whether or not `digest` is signed, so handlers that rely
+    /// on its contents should check `Verification::covers("digest")`. Reading the body fails with
+    /// `Error::IO`.
+    pub fn verify_and_read_body<G, R>(
+        self,
+        headers: &[(&str, &str)],
+        method: &str,
+        path: &str,
+        query: Option<&str>,
+        mut body: R,
+        key_getter: G,
+    ) -> Result<(Verification, Vec<u8>), Error>
+    where
+        G: GetKey,
+        R: Read,
+    {
+        let signs_digest = self.signs_digest();
+
+        let vah = CheckSignedHeader {
+            auth_header: self,
+            headers: headers,
+            pseudo_header: (REQUEST_TARGET, request_target(method, path, query)),
+            body: None,
+        };
+
+        let mut verification = vah.verify(key_getter)?;
+
+        let mut bytes = Vec::new();
+        body.read_to_end(&mut bytes)?;
+
+        if signs_digest {
+            vah.check_digest(&bytes)?;
+            verification.digest_checked = true;
+        }
+
+        Ok((verification, bytes))
+    }
+
+    /// Try to verify the current `SignedHeader`, only reading the body if the `digest` header is
+    /// signed.
+    ///
+    /// `read_body` is called at most once, after the signature has been verified, so requests
+    /// whose signature doesn't cover `digest`, such as most GET requests, are verified without
+    /// touching their body. This suits bodies that are streams, which can't be read twice, or
+    /// are too large to hold in memory just to verify a signature that doesn't depend on them.
+    pub fn verify_with_lazy_body<G, F, B>(
+        self,
+        headers: &[(&str, &str)],
+        method: &str,
+        path: &str,
+        query: Option<&str>,
+        read_body: F,
+        key_getter: G,
+    ) -> Result<Verification, VerificationError>
+    where
+        G: GetKey,
+        F: FnOnce() -> Result<B, VerificationError>,
+        B: AsRef<[u8]>,
+    {
+        let signs_digest = self.signs_digest();
+
+        let vah = CheckSignedHeader {
+            auth_header: self,
+            headers: headers,
+            pseudo_header: (REQUEST_TARGET, request_target(method, path, query)),
+            body: None,
+        };
+
+        let mut verification = vah.verify(key_getter)?;
+
+        if signs_digest {
+            let body = read_body()?;
+            vah.check_digest(body.as_ref())?;
+            verification.digest_checked = true;
+        }
+
+        Ok(verification)
+    }
+
+    /// Try to verify the current `SignedHeader` as the signature of a response.
+    ///
+    /// Responses have no `(request-target)`. Instead, the `(status)` pseudo-header holds the
+    /// three-digit status code, such as `200`, and is signed in its place. Signatures that name
+    /// `(request-target)` fail with `VerificationError::MissingHeaders`.
+    pub fn verify_response<G>(
+        self,
+        headers: &[(&str, &str)],
+        status: u16,
+        key_getter: G,
+    ) -> Result<Verification, VerificationError>
+    where
+        G: GetKey,
+    {
+        let vah = CheckSignedHeader {
+            auth_header: self,
+            headers: headers,
+            pseudo_header: (STATUS, status.to_string()),
+            body: None,
+        };
+
+        vah.verify(key_getter)
+    }
+
+    /// Try to verify the current `SignedHeader` as the signature of a response, and the response
+    /// body.
+    ///
+    /// If the `digest` header is signed, the Digest header is checked against `body`, and this
+    /// fails with `VerificationError::DigestMismatch` if they differ.
+    pub fn verify_response_with_body<G>(
+        self,
+        headers: &[(&str, &str)],
+        status: u16,
+        body: &[u8],
+        key_getter: G,
+    ) -> Result<Verification, VerificationError>
+    where
+        G: GetKey,
+    {
+        let vah = CheckSignedHeader {
+            auth_header: self,
+            headers: headers,
+            pseudo_header: (STATUS, status.to_string()),
+            body: Some(body),
+        };
+
+        vah.verify(key_getter)
+    }
+}
+
+/// The details of a verified signature, returned by `verify`, `verify_any`, and the verification
+/// methods of `SignedHeader` and `VerifyHeader`.
+///
+/// Verification fails with a `VerificationError`, so a `Verification` always describes a valid
+/// signature. It says which key signed the request, and what that signature covers, so
+/// applications can make finer decisions, such as only trusting a key for requests whose `digest`
+/// it signed, or log which key signed each request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Verification {
+    key_id: String,
+    algorithm: SignatureAlgorithm,
+    headers: Vec<String>,
+    created: Option<u64>,
+    expires: Option<u64>,
+    digest_checked: bool,
+    timestamps_checked: bool,
+}
+
+impl Verification {
+    /// The `keyId` of the key that signed the request
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// The algorithm the signature was created with
+    pub fn algorithm(&self) -> &SignatureAlgorithm {
+        &self.algorithm
+    }
+
+    /// The names of the headers the signature covers, in signing order, including
+    /// pseudo-headers such as `(request-target)`
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    /// Whether the signature covers the named header. Names are matched case-insensitively.
+    pub fn covers(&self, header_name: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|header| header.eq_ignore_ascii_case(header_name))
+    }
+
+    /// The `created` parameter of the signature, if present
+    pub fn created(&self) -> Option<u64> {
+        self.created
+    }
+
+    /// The `expires` parameter of the signature, if present
+    pub fn expires(&self) -> Option<u64> {
+        self.expires
+    }
+
+    /// Whether a signed Digest header was checked against the body.
+    ///
+    /// This is `false` when `digest` isn't signed, or when the signature was verified without the
+    /// body, as with `SignedHeader::verify`.
+    pub fn digest_checked(&self) -> bool {
+        self.digest_checked
+    }
+
+    /// Whether the `created` and `expires` parameters were checked against the current time,
+    /// which is skipped after `SignedHeader::without_timestamp_checks`
+    pub fn timestamps_checked(&self) -> bool {
+        self.timestamps_checked
+    }
+
+    /// Whether every check that applies to the signature was made.
+    ///
+    /// The signature itself is always valid, but a signed Digest header that wasn't checked
+    /// against the body, or timestamps that weren't checked, leave part of the request
+    /// unverified.
+    pub fn is_valid(&self) -> bool {
+        self.timestamps_checked && (self.digest_checked || !self.covers(DIGEST))
+    }
+}
+
+/// Verify a raw `signature` over `data`, with the same cryptography that verifies HTTP Signatures.
+///
+/// Nothing about HTTP is involved, so this can check signatures over other payloads, such as
+/// webhook bodies, made with `sign_bytes`. `public_key` is read like the keys from a `GetKey`: a
+/// PKCS#1 DER public key for RSA, the raw 32 bytes of the public key for Ed25519, or the shared
+/// secret for HMAC. PEM and SubjectPublicKeyInfo keys can be converted with `PublicKey`, whose
+/// `as_bytes` are in this form. A signature that doesn't match returns `Ok(false)`, while keys
+/// that can't be read or don't suit `algorithm` are errors.
+///
+/// ### Example
+/// ```rust
+/// # use std::io::Cursor;
+/// use http_signatures::{sign_bytes, verify_bytes, ShaSize, SignatureAlgorithm};
+///
+/// let algorithm = SignatureAlgorithm::HMAC(ShaSize::TwoFiftySix);
+/// let key = b"shared secret".to_vec();
+///
+/// let signature = sign_bytes(b"webhook body", Cursor::new(key.clone()), algorithm.clone())
+///     .unwrap();
+///
+/// assert!(verify_bytes(b"webhook body", &signature, Cursor::new(key), algorithm).unwrap());
+/// ```
+pub fn verify_bytes<T>(
+    data: &[u8],
+    signature: &[u8],
+    public_key: T,
+    algorithm: SignatureAlgorithm,
+) -> Result<bool, VerificationError>
+where
+    T: Read,
+{
+    match CheckSignedHeader::verify_raw(&algorithm, public_key, data, signature) {
+        Ok(()) => Ok(true),
+        Err(VerificationError::BadSignature) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Verify a request from its raw parts, returning the details of the signature, including the
+/// `keyId` of the key that signed it.
+///
+/// `signature_header` is the value of the request's Authorization or Signature header. This does
+/// the whole verification, so it can be used with request types this crate doesn't support, by
+/// implementing `HeaderSource` for their headers.
+///
+/// ### Example
+/// ```rust
+/// # use std::io::Cursor;
+/// # use http_signatures::{Error, VerificationError};
+/// # use http_signatures::prelude::*;
+/// # struct MyKeyGetter;
+/// # impl GetKey for MyKeyGetter {
+/// #     type Key = Cursor<Vec<u8>>;
+/// #     type Error = VerificationError;
+/// #     fn get_key(self, _: &str) -> Result<Self::Key, Self::Error> {
+/// #         Ok(Cursor::new(b"secret".to_vec()))
+/// #     }
+/// # }
+/// # fn run(auth_header: &str) -> Result<(), Error> {
+/// let headers = vec![("Date", "Tue, 07 Jun 2014 20:51:35 GMT")];
+///
+/// let verification = http_signatures::verify(
+///     "GET",
+///     "/foo",
+///     None,
+///     &headers,
+///     auth_header,
+///     MyKeyGetter,
+/// )?;
+///
+/// println!("Signed by {}", verification.key_id());
+/// # Ok(())
+/// # }
+/// ```
+pub fn verify<H, G>(
+    method: &str,
+    path: &str,
+    query: Option<&str>,
+    headers: &H,
+    signature_header: &str,
+    key_getter: G,
+) -> Result<Verification, VerificationError>
+where
+    H: HeaderSource + ?Sized,
+    G: GetKey,
+{
+    let header_pairs = headers.header_pairs()?;
+
+    SignedHeader::new(signature_header)?.verify(&header_pairs, method, path, query, key_getter)
 }
 
 impl<'a> TryFrom<&'a str> for SignedHeader<'a> {
     type Error = DecodeError;
 
     fn try_from(s: &'a str) -> Result<Self, Self::Error> {
-        let s = s.trim_left_matches("Signature ");
-        let key_value = s.split(',')
-            .filter_map(|item| {
-                let eq_index = item.find('=')?;
-                let tup = item.split_at(eq_index);
-                let val = tup.1.get(1..)?;
-                Some((tup.0, val))
-            })
-            .collect::<HashMap<&str, &str>>();
-
-        let key_id = key_value
-            .get(KEY_ID)
-            .ok_or(DecodeError::MissingKey(KEY_ID))?
-            .trim_left_matches('"')
-            .trim_right_matches('"');
+        let key_value = parse_params(strip_scheme(s.trim()))?;
 
-        let header_keys = key_value
-            .get(HEADERS)
-            .unwrap_or(&DATE)
-            .trim_left_matches('"')
-            .trim_right_matches('"')
-            .split(' ')
-            .collect();
+        let key_id = *key_value
+            .get(KEY_ID)
+            .ok_or(DecodeError::MissingKey(KEY_ID))?;
 
-        let algorithm = (*key_value
+        let algorithm = key_value
             .get(ALGORITHM)
             .ok_or(DecodeError::MissingKey(ALGORITHM))?
-            .trim_left_matches('"')
-            .trim_right_matches('"'))
             .parse()?;
 
-        let sig_string: String = key_value
+        let created = parse_timestamp(&key_value, CREATED_PARAM)?;
+        let expires = parse_timestamp(&key_value, EXPIRES_PARAM)?;
+
+        // Later revisions of the draft default to `(created)` when it is present
+        let default_headers = if created.is_some() { CREATED } else { DATE };
+
+        let header_keys = key_value
+            .get(HEADERS)
+            .map(|headers| *headers)
+            .unwrap_or(default_headers)
+            .split_whitespace()
+            .collect();
+
+        let sig_string = *key_value
             .get(SIGNATURE)
-            .ok_or(DecodeError::MissingKey(SIGNATURE))?
-            .trim_left_matches('"')
-            .trim_right_matches('"')
-            .into();
+            .ok_or(DecodeError::MissingKey(SIGNATURE))?;
 
-        let signature = decode(&sig_string).map_err(|_| DecodeError::NotBase64)?;
+        let signature = decode(sig_string).map_err(|_| DecodeError::NotBase64)?;
 
         Ok(SignedHeader {
             key_id,
             header_keys,
             algorithm,
+            created,
+            expires,
             signature,
+            max_skew: Duration::from_secs(DEFAULT_MAX_SKEW_SECS),
+            max_age: None,
+            check_timestamps: true,
+            now: None,
+            required_headers: Vec::new(),
         })
     }
 }
 
+/// The parameters of an Authorization or Signature header, owned rather than borrowed from the
+/// header like `SignedHeader`, so they can be kept after the request is gone.
+///
+/// ### Example
+/// ```rust
+/// use http_signatures::SignatureHeader;
+///
+/// let header = SignatureHeader::parse(concat!(
+///     r#"Signature keyId="key-1",algorithm="hmac-sha256","#,
+///     r#"headers="(request-target) date",signature="AQID""#,
+/// )).unwrap();
+///
+/// assert_eq!(header.key_id, "key-1");
+/// assert_eq!(header.headers, vec!["(request-target)", "date"]);
+/// assert_eq!(header.signature, vec![1, 2, 3]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SignatureHeader {
+    /// The `keyId` parameter
+    pub key_id: String,
+    /// The `algorithm` parameter
+    pub algorithm: SignatureAlgorithm,
+    /// The names of the signed headers, in signing order
+    pub headers: Vec<String>,
+    /// The `created` parameter, if present
+    pub created: Option<u64>,
+    /// The `expires` parameter, if present
+    pub expires: Option<u64>,
+    /// The decoded `signature` parameter
+    pub signature: Vec<u8>,
+}
+
+impl SignatureHeader {
+    /// Parse the value of a Signature header, or of an Authorization header using the
+    /// `Signature` scheme.
+    ///
+    /// This fails with `DecodeError::MissingKey` if a required parameter is missing,
+    /// `DecodeError::InvalidAlgorithm` if the algorithm isn't supported,
+    /// `DecodeError::InvalidTimestamp` if `created` or `expires` isn't a timestamp,
+    /// `DecodeError::NotBase64` if the signature isn't base64, and `DecodeError::Malformed` if the
+    /// header can't be split into parameters.
+    pub fn parse(s: &str) -> Result<Self, DecodeError> {
+        SignedHeader::new(s).map(SignatureHeader::from)
+    }
+}
+
+impl<'a> From<SignedHeader<'a>> for SignatureHeader {
+    fn from(signed_header: SignedHeader<'a>) -> Self {
+        SignatureHeader {
+            key_id: signed_header.key_id.to_owned(),
+            algorithm: signed_header.algorithm,
+            headers: signed_header
+                .header_keys
+                .iter()
+                .map(|header| (*header).to_owned())
+                .collect(),
+            created: signed_header.created,
+            expires: signed_header.expires,
+            signature: signed_header.signature,
+        }
+    }
+}
+
+/// The `RemappedHeaders` struct holds the headers of a request after rewriting the ones a trusted
+/// proxy has changed, so the signing string matches the request as it was signed.
+///
+/// Reverse proxies often replace the Host header, and pass the original on in a header such as
+/// `X-Forwarded-Host`. Trusting that header means believing whatever it says, so this is only
+/// safe when every request passes through the proxy, and the proxy replaces, rather than appends
+/// to, any such header sent by the client. Otherwise a client could sign a request for one host
+/// and have it accepted for another.
+///
+/// `RemappedHeaders` is a `HeaderSource`, so it can be passed to `verify`.
+///
+/// ### Example
+/// ```rust
+/// use http_signatures::RemappedHeaders;
+/// use http_signatures::prelude::*;
+///
+/// let headers = vec![("Host", "backend:8080"), ("X-Forwarded-Host", "example.org")];
+///
+/// let remapped = RemappedHeaders::new(&headers)
+///     .unwrap()
+///     .trust_as("x-forwarded-host", "host");
+///
+/// assert_eq!(remapped.header_pairs().unwrap(), vec![("host", "example.org")]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RemappedHeaders {
+    headers: Vec<(String, String)>,
+}
+
+impl RemappedHeaders {
+    /// Copy the headers of a request, with lowercased names, so they can be rewritten
+    pub fn new<H>(headers: &H) -> Result<Self, VerificationError>
+    where
+        H: HeaderSource + ?Sized,
+    {
+        Ok(RemappedHeaders {
+            headers: headers
+                .header_pairs()?
+                .into_iter()
+                .map(|(name, value)| (name.to_lowercase(), value.to_owned()))
+                .collect(),
+        })
+    }
+
+    /// Verify the value of the `from` header as the `to` header.
+    ///
+    /// If a `from` header is present, every `to` header is dropped, and the `from` headers are
+    /// renamed to `to`. Otherwise the headers are left as they are.
+    pub fn trust_as(mut self, from: &str, to: &str) -> Self {
+        let from = from.to_lowercase();
+        let to = to.to_lowercase();
+
+        if self.headers.iter().any(|&(ref name, _)| *name == from) {
+            self.headers.retain(|&(ref name, _)| *name != to);
+
+            for header in &mut self.headers {
+                if header.0 == from {
+                    header.0 = to.clone();
+                }
+            }
+        }
+
+        self
+    }
+
+    /// Rewrite the value of every header called `name`.
+    pub fn map_value<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: Fn(&str) -> String,
+    {
+        let name = name.to_lowercase();
+
+        for header in &mut self.headers {
+            if header.0 == name {
+                header.1 = f(&header.1);
+            }
+        }
+
+        self
+    }
+}
+
+impl HeaderSource for RemappedHeaders {
+    fn header_pairs(&self) -> Result<Vec<(&str, &str)>, VerificationError> {
+        self.headers.header_pairs()
+    }
+}
+
+/// Read the `keyId` of an Authorization or Signature header, without checking the rest of it.
+///
+/// This is much cheaper than verification, so it suits routing, rate limiting and logging before
+/// the key is looked up. The signature isn't decoded and the other parameters aren't checked, so
+/// the key id must not be trusted until the header has been verified.
+///
+/// ### Example
+/// ```rust
+/// use http_signatures::peek_key_id;
+///
+/// let key_id = peek_key_id(r#"Signature keyId="rsa-key-1",signature="...""#).unwrap();
+///
+/// assert_eq!(key_id, "rsa-key-1");
+/// ```
+pub fn peek_key_id(signature_header: &str) -> Result<String, DecodeError> {
+    parse_params(strip_scheme(signature_header.trim()))?
+        .get(KEY_ID)
+        .map(|key_id| (*key_id).to_owned())
+        .ok_or(DecodeError::MissingKey(KEY_ID))
+}
+
+/// Verify a request carrying several signatures, such as one from each proxy it passed through,
+/// and return the details of the first signature that verifies.
+///
+/// `signature_headers` holds the values of every Signature header, which are split with
+/// `SignedHeader::parse_all`. Signatures whose key the `key_getter` doesn't know are skipped, so
+/// a request verifies as long as one signature is from a known key. If none verify, this returns
+/// the error of the first signature with a known key, or `VerificationError::GetKey` if no key
+/// was known.
+pub fn verify_any<H, G>(
+    method: &str,
+    path: &str,
+    query: Option<&str>,
+    headers: &H,
+    signature_headers: &[&str],
+    key_getter: G,
+) -> Result<Verification, VerificationError>
+where
+    H: HeaderSource + ?Sized,
+    G: GetKey + Clone,
+{
+    let signed_headers = SignedHeader::parse_all(signature_headers.iter().cloned())?;
+    let header_pairs = headers.header_pairs()?;
+    let mut error = None;
+
+    for signed_header in signed_headers {
+        match signed_header.verify(&header_pairs, method, path, query, key_getter.clone()) {
+            Ok(verification) => return Ok(verification),
+            Err(VerificationError::GetKey) => (),
+            Err(e) => if error.is_none() {
+                error = Some(e);
+            },
+        }
+    }
+
+    Err(error.unwrap_or(VerificationError::GetKey))
+}
+
+/// Whether an Authorization header uses the `Signature` auth-scheme, rather than some other
+/// credentials that must be left alone.
+pub(crate) fn is_signature_scheme(s: &str) -> bool {
+    match s.find(char::is_whitespace) {
+        Some(index) => s[..index].eq_ignore_ascii_case(SCHEME),
+        None => false,
+    }
+}
+
+/// Authorization headers start with the `Signature` auth-scheme, which is case-insensitive, and
+/// Signature headers start directly with the parameters.
+fn strip_scheme(s: &str) -> &str {
+    match s.find(char::is_whitespace) {
+        Some(index) if s[..index].eq_ignore_ascii_case(SCHEME) => s[index..].trim_left(),
+        _ => s,
+    }
+}
+
+fn parse_timestamp(
+    key_value: &HashMap<&str, &str>,
+    key: &'static str,
+) -> Result<Option<u64>, DecodeError> {
+    match key_value.get(key) {
+        Some(timestamp) => timestamp
+            .parse()
+            .map(Some)
+            .map_err(|_| DecodeError::InvalidTimestamp(key)),
+        None => Ok(None),
+    }
+}
+
+/// Split the parameters of a signature header into key/value pairs.
+///
+/// Whitespace around the `,` and `=` separators is ignored, and quoted values may contain commas
+/// and spaces.
+fn parse_params(s: &str) -> Result<HashMap<&str, &str>, DecodeError> {
+    Ok(params(s)?
+        .into_iter()
+        .map(|(_, key, value)| (key, value))
+        .collect())
+}
+
+/// Split a header value holding one or more signatures into the parameters of each signature.
+///
+/// Repeated Signature headers are joined with commas, so a signature ends where one of its
+/// parameters appears again.
+fn split_signatures(s: &str) -> Result<Vec<&str>, DecodeError> {
+    let mut signatures = Vec::new();
+    let mut seen = Vec::new();
+    let mut start = 0;
+
+    for (offset, key, _) in params(s)? {
+        if seen.contains(&key) {
+            signatures.push(s[start..offset].trim_right_matches(|c: char| {
+                c == ',' || c.is_whitespace()
+            }));
+            seen.clear();
+            start = offset;
+        }
+
+        seen.push(key);
+    }
+
+    signatures.push(&s[start..]);
+
+    Ok(signatures)
+}
+
+/// The parameters of a signature header, along with the offset each one starts at
+fn params(s: &str) -> Result<Vec<(usize, &str, &str)>, DecodeError> {
+    let mut params = Vec::new();
+    let mut rest = s;
+
+    loop {
+        rest = rest.trim_left_matches(|c: char| c == ',' || c.is_whitespace());
+
+        if rest.is_empty() {
+            break;
+        }
+
+        let offset = s.len() - rest.len();
+        let eq_index = rest.find('=').ok_or(DecodeError::Malformed)?;
+        let key = rest[..eq_index].trim();
+
+        if key.is_empty() {
+            return Err(DecodeError::Malformed);
+        }
+
+        rest = rest[eq_index + 1..].trim_left();
+
+        let value = if rest.starts_with('"') {
+            let end_quote = rest[1..].find('"').ok_or(DecodeError::Malformed)? + 1;
+            let value = &rest[1..end_quote];
+            rest = rest[end_quote + 1..].trim_left();
+
+            if !(rest.is_empty() || rest.starts_with(',')) {
+                return Err(DecodeError::Malformed);
+            }
+
+            value
+        } else {
+            let end = rest.find(',').unwrap_or_else(|| rest.len());
+            let value = rest[..end].trim();
+            rest = &rest[end..];
+
+            value
+        };
+
+        params.push((offset, key, value));
+    }
+
+    Ok(params)
+}
+
 #[derive(Debug)]
 struct CheckSignedHeader<'a> {
     auth_header: SignedHeader<'a>,
     headers: &'a [(&'a str, &'a str)],
-    method: &'a str,
-    path: &'a str,
-    query: Option<&'a str>,
+    /// `(request-target)` for requests, or `(status)` for responses, and its value
+    pseudo_header: (&'static str, String),
+    body: Option<&'a [u8]>,
 }
 
 impl<'a> CheckSignedHeader<'a> {
-    pub fn verify<G>(&self, key_getter: G) -> Result<(), VerificationError>
+    pub fn verify<G>(&self, key_getter: G) -> Result<Verification, VerificationError>
+    where
+        G: GetKey,
+    {
+        let span = trace::verify_span(self.auth_header.key_id, &self.auth_header.algorithm);
+        let _guard = span.enter();
+        let verified = self.check(key_getter);
+        trace::verification(&verified);
+
+        verified
+    }
+
+    fn check<G>(&self, key_getter: G) -> Result<Verification, VerificationError>
     where
         G: GetKey,
     {
-        let key: G::Key = key_getter
-            .get_key(self.auth_header.key_id)
+        self.auth_header.check_timestamps()?;
+        self.auth_header.check_required_headers()?;
+
+        if let Some(algorithm) = key_getter.key_algorithm(self.auth_header.key_id) {
+            if algorithm != self.auth_header.algorithm {
+                return Err(VerificationError::AlgorithmMismatch);
+            }
+        }
+
+        let keys: Vec<G::Key> = key_getter
+            .get_keys(self.auth_header.key_id)
             .map_err(|_| VerificationError::GetKey)?;
 
-        let headers: HashMap<String, Vec<&str>> = self.headers.iter().fold(
+        let headers: HashMap<String, Vec<String>> = self.headers.iter().fold(
             HashMap::new(),
             |mut acc, &(key, value)| {
                 acc.entry(key.to_lowercase())
                     .or_insert_with(Vec::new)
-                    .push(value);
+                    .push(unfold_header_value(value));
 
                 acc
             },
@@ -164,14 +1065,15 @@ impl<'a> CheckSignedHeader<'a> {
             .map(|(key, value)| (key.as_ref(), value.join(", ")))
             .collect();
 
-        headers.insert(
-            REQUEST_TARGET,
-            if let Some(query) = self.query {
-                format!("{} {}?{}", self.method.to_lowercase(), self.path, query,)
-            } else {
-                format!("{} {}", self.method.to_lowercase(), self.path,)
-            },
-        );
+        headers.insert(self.pseudo_header.0, self.pseudo_header.1.clone());
+
+        if let Some(created) = self.auth_header.created {
+            headers.insert(CREATED, created.to_string());
+        }
+
+        if let Some(expires) = self.auth_header.expires {
+            headers.insert(EXPIRES, expires.to_string());
+        }
 
         let signing_vec = self.auth_header.header_keys.iter().fold(
             (Vec::new(), Vec::new()),
@@ -192,25 +1094,110 @@ impl<'a> CheckSignedHeader<'a> {
 
         let signing_string = signing_vec.0.join("\n");
 
-        match self.auth_header.algorithm {
-            SignatureAlgorithm::RSA(ref sha_size) => Self::verify_rsa(
-                key,
-                sha_size,
-                signing_string.as_bytes(),
-                &self.auth_header.signature,
-            ),
-            SignatureAlgorithm::HMAC(ref sha_size) => Self::verify_hmac(
-                key,
-                sha_size,
-                signing_string.as_bytes(),
-                &self.auth_header.signature,
-            ),
+        // During key rotation there may be several candidate keys, and any of them may have
+        // signed the request. If none did, the error from the last candidate is returned.
+        let mut verified = Err(VerificationError::GetKey);
+
+        for key in keys {
+            verified = self.verify_signature(key, signing_string.as_bytes());
+
+            if verified.is_ok() {
+                break;
+            }
+        }
+
+        verified?;
+
+        let mut digest_checked = false;
+
+        if let Some(body) = self.body {
+            if self.auth_header.signs_digest() {
+                // The digest header was signed, so it is present in the headers map
+                Digest::verify_header(&headers[DIGEST], body)?;
+                digest_checked = true;
+            }
+        }
+
+        Ok(Verification {
+            key_id: self.auth_header.key_id.to_owned(),
+            algorithm: self.auth_header.algorithm.clone(),
+            headers: self.auth_header
+                .header_keys
+                .iter()
+                .map(|header| (*header).to_owned())
+                .collect(),
+            created: self.auth_header.created,
+            expires: self.auth_header.expires,
+            digest_checked: digest_checked,
+            timestamps_checked: self.auth_header.check_timestamps,
+        })
+    }
+
+    /// Check the Digest header against a body read after the signature was verified
+    fn check_digest(&self, body: &[u8]) -> Result<(), VerificationError> {
+        let values: Vec<String> = self.headers
+            .iter()
+            .filter(|&&(key, _)| key.eq_ignore_ascii_case(DIGEST))
+            .map(|&(_, value)| unfold_header_value(value))
+            .collect();
+
+        if values.is_empty() {
+            return Err(VerificationError::MissingHeaders(DIGEST.to_owned()));
+        }
+
+        Digest::verify_header(&values.join(", "), body)
+    }
+
+    fn verify_signature<T>(&self, key: T, signing_string: &[u8]) -> Result<(), VerificationError>
+    where
+        T: Read,
+    {
+        Self::verify_raw(
+            &self.auth_header.algorithm,
+            key,
+            signing_string,
+            &self.auth_header.signature,
+        )
+    }
+
+    fn verify_raw<T>(
+        algorithm: &SignatureAlgorithm,
+        key: T,
+        signing_string: &[u8],
+        sig: &[u8],
+    ) -> Result<(), VerificationError>
+    where
+        T: Read,
+    {
+        match *algorithm {
+            SignatureAlgorithm::RSA(ref sha_size) => {
+                let parameters = match *sha_size {
+                    ShaSize::TwoFiftySix => &signature::RSA_PKCS1_2048_8192_SHA256,
+                    ShaSize::ThreeEightyFour => &signature::RSA_PKCS1_2048_8192_SHA384,
+                    ShaSize::FiveTwelve => &signature::RSA_PKCS1_2048_8192_SHA512,
+                };
+
+                Self::verify_rsa(key, parameters, signing_string, sig)
+            }
+            SignatureAlgorithm::RSAPSS(ref sha_size) => {
+                let parameters = match *sha_size {
+                    ShaSize::TwoFiftySix => &signature::RSA_PSS_2048_8192_SHA256,
+                    ShaSize::ThreeEightyFour => &signature::RSA_PSS_2048_8192_SHA384,
+                    ShaSize::FiveTwelve => &signature::RSA_PSS_2048_8192_SHA512,
+                };
+
+                Self::verify_rsa(key, parameters, signing_string, sig)
+            }
+            SignatureAlgorithm::HMAC(ref sha_size) => {
+                Self::verify_hmac(key, sha_size, signing_string, sig)
+            }
+            SignatureAlgorithm::Ed25519 => Self::verify_ed25519(key, signing_string, sig),
         }
     }
 
     fn verify_rsa<T>(
         mut key: T,
-        sha_size: &ShaSize,
+        parameters: &'static signature::RSAParameters,
         signing_string: &[u8],
         sig: &[u8],
     ) -> Result<(), VerificationError>
@@ -221,40 +1208,25 @@ impl<'a> CheckSignedHeader<'a> {
         let mut public_key_der = Vec::new();
         key.read_to_end(&mut public_key_der)
             .map_err(|_| VerificationError::ReadKey)?;
+
+        if !is_rsa_public_key(&public_key_der) {
+            return Err(VerificationError::BadPublicKey);
+        }
+
         let public_key_der = Input::from(&public_key_der);
         let message = Input::from(signing_string);
         let signature = Input::from(sig);
 
-        match *sha_size {
-            ShaSize::TwoFiftySix => {
-                signature::verify(
-                    &signature::RSA_PKCS1_2048_8192_SHA256,
-                    public_key_der,
-                    message,
-                    signature,
-                ).map_err(|Unspecified| VerificationError::BadSignature)?;
-            }
-            ShaSize::ThreeEightyFour => {
-                signature::verify(
-                    &signature::RSA_PKCS1_2048_8192_SHA384,
-                    public_key_der,
-                    message,
-                    signature,
-                ).map_err(|Unspecified| VerificationError::BadSignature)?;
-            }
-            ShaSize::FiveTwelve => {
-                signature::verify(
-                    &signature::RSA_PKCS1_2048_8192_SHA512,
-                    public_key_der,
-                    message,
-                    signature,
-                ).map_err(|Unspecified| VerificationError::BadSignature)?;
-            }
-        }
+        signature::verify(parameters, public_key_der, message, signature)
+            .map_err(|Unspecified| VerificationError::BadSignature)?;
 
         Ok(())
     }
 
+    /// HMAC signatures are verified by recomputing the HMAC of the signing string with the shared
+    /// secret. Ring compares the result with the provided signature in constant time.
+    ///
+    /// Public keys are rejected as secrets, since anyone could compute an HMAC with them.
     fn verify_hmac<T>(
         mut key: T,
         sha_size: &ShaSize,
@@ -267,6 +1239,11 @@ impl<'a> CheckSignedHeader<'a> {
         let mut hmac_key = Vec::new();
         key.read_to_end(&mut hmac_key)
             .map_err(|_| VerificationError::ReadKey)?;
+
+        if is_public_key(&hmac_key) {
+            return Err(VerificationError::AlgorithmMismatch);
+        }
+
         let hmac_key = hmac::SigningKey::new(
             match *sha_size {
                 ShaSize::TwoFiftySix => &digest::SHA256,
@@ -281,4 +1258,30 @@ impl<'a> CheckSignedHeader<'a> {
 
         Ok(())
     }
+
+    fn verify_ed25519<T>(
+        mut key: T,
+        signing_string: &[u8],
+        sig: &[u8],
+    ) -> Result<(), VerificationError>
+    where
+        T: Read,
+    {
+        let mut public_key = Vec::new();
+        key.read_to_end(&mut public_key)
+            .map_err(|_| VerificationError::ReadKey)?;
+
+        if public_key.len() != ED25519_PUBLIC_KEY_LEN {
+            return Err(VerificationError::BadPublicKey);
+        }
+
+        signature::verify(
+            &signature::ED25519,
+            Input::from(&public_key),
+            Input::from(signing_string),
+            Input::from(sig),
+        ).map_err(|Unspecified| VerificationError::BadSignature)?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,125 @@
+// This file is part of HTTP Signatures
+
+// HTTP Signatures is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// HTTP Signatures is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with HTTP Signatures  If not, see <http://www.gnu.org/licenses/>.
+
+//! The test vectors from Appendix C of draft-cavage-http-signatures-09.
+//!
+//! Every test signs the example request from the appendix:
+//!
+//! ```text
+//! POST /foo?param=value&pet=dog HTTP/1.1
+//! Host: example.com
+//! Date: Sun, 05 Jan 2014 21:31:40 GMT
+//! Content-Type: application/json
+//! Digest: SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=
+//! Content-Length: 18
+//!
+//! {"hello": "world"}
+//! ```
+//!
+//! Only the signing strings and headers are compared. The appendix signs with a 1024 bit RSA key,
+//! which Ring 0.11 refuses to sign or verify with, so its signatures can't be reproduced.
+
+extern crate http_signatures;
+
+use std::collections::BTreeMap;
+
+use http_signatures::{Digest, HttpSignature, ShaSize, SignatureAlgorithm, SignedHeader,
+                      REQUEST_TARGET};
+
+const BODY: &str = r#"{"hello": "world"}"#;
+
+fn example_request(header_names: &[&str]) -> HttpSignature<&'static [u8]> {
+    let mut headers = BTreeMap::new();
+    headers.insert(REQUEST_TARGET.into(), vec!["post /foo?param=value&pet=dog".into()]);
+    headers.insert("host".into(), vec!["example.com".into()]);
+    headers.insert("date".into(), vec!["Sun, 05 Jan 2014 21:31:40 GMT".into()]);
+    headers.insert("content-type".into(), vec!["application/json".into()]);
+    headers.insert(
+        "digest".into(),
+        vec!["SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=".into()],
+    );
+    headers.insert("content-length".into(), vec!["18".into()]);
+
+    HttpSignature::new(
+        "Test".into(),
+        &b""[..],
+        SignatureAlgorithm::RSA(ShaSize::TwoFiftySix),
+        headers,
+    ).unwrap()
+        .with_signed_headers(header_names)
+        .unwrap()
+}
+
+#[test]
+fn example_digest() {
+    assert_eq!(
+        Digest::from_body(BODY.as_bytes(), ShaSize::TwoFiftySix).to_string(),
+        "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE="
+    );
+}
+
+/// C.1. Default Test
+#[test]
+fn default_test() {
+    let http_sig = example_request(&["date"]);
+
+    assert_eq!(http_sig.signing_string(), "date: Sun, 05 Jan 2014 21:31:40 GMT");
+
+    // The headers parameter may be left out when only the Date header is signed
+    let header = SignedHeader::new(r#"keyId="Test",algorithm="rsa-sha256",signature="AQID""#)
+        .unwrap();
+
+    assert_eq!(header.header_keys(), &["date"]);
+}
+
+/// C.2. Basic Test
+#[test]
+fn basic_test() {
+    let http_sig = example_request(&[REQUEST_TARGET, "host", "date"]);
+
+    assert_eq!(
+        http_sig.signing_string(),
+        concat!(
+            "(request-target): post /foo?param=value&pet=dog\n",
+            "host: example.com\n",
+            "date: Sun, 05 Jan 2014 21:31:40 GMT"
+        )
+    );
+}
+
+/// C.3. All Headers Test
+#[test]
+fn all_headers_test() {
+    let http_sig = example_request(&[
+        REQUEST_TARGET,
+        "host",
+        "date",
+        "content-type",
+        "digest",
+        "content-length",
+    ]);
+
+    assert_eq!(
+        http_sig.signing_string(),
+        concat!(
+            "(request-target): post /foo?param=value&pet=dog\n",
+            "host: example.com\n",
+            "date: Sun, 05 Jan 2014 21:31:40 GMT\n",
+            "content-type: application/json\n",
+            "digest: SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=\n",
+            "content-length: 18"
+        )
+    );
+}